@@ -35,9 +35,20 @@ use system::ensure_signed;
 use runtime_support::{StorageMap};
 use runtime_support::dispatch::Result;
 
+/// Notified after an account starts or stops delegating
+pub trait OnDelegationChange<AccountId> {
+	fn on_delegation_change(who: &AccountId);
+}
+
+impl<AccountId> OnDelegationChange<AccountId> for () {
+	fn on_delegation_change(_who: &AccountId) {}
+}
+
 pub trait Trait: system::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Handler for accounts changing their delegate
+	type OnDelegationChange: OnDelegationChange<Self::AccountId>;
 }
 
 decl_module! {
@@ -58,6 +69,7 @@ decl_module! {
 				<DelegatesTo<T>>::insert(to.clone(), vec![_sender.clone()]);
 			}
 			
+			T::OnDelegationChange::on_delegation_change(&_sender);
 			// Fire delegation event
 			Self::deposit_event(RawEvent::Delegated(_sender, to));
 
@@ -72,8 +84,7 @@ decl_module! {
 			<DelegatesOf<T>>::remove(&_sender);
 			// Update the delegates of to remove _sender
 			if let Some(mut delegates) = <DelegatesTo<T>>::get(from.clone()) {
-				let index = delegates.iter().position(|d| d == &_sender.clone()).unwrap();
-				delegates.remove(index);
+				delegates.retain(|d| d != &_sender);
 
 				if delegates.len() == 0 {
 					<DelegatesTo<T>>::remove(from.clone());
@@ -81,6 +92,7 @@ decl_module! {
 					<DelegatesTo<T>>::insert(from.clone(), delegates);	
 				}
 			}
+			T::OnDelegationChange::on_delegation_change(&_sender);
 			// Fire delegation event
 			Self::deposit_event(RawEvent::Undelegated(_sender, from));
 
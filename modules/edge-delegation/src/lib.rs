@@ -41,7 +41,7 @@ extern crate srml_system as system;
 
 
 pub mod delegation;
-pub use delegation::{Module, Trait, RawEvent, Event};
+pub use delegation::{Module, Trait, RawEvent, Event, OnDelegationChange};
 
 // Tests for Delegation Module
 #[cfg(test)]
@@ -95,6 +95,7 @@ mod tests {
 
 	impl Trait for Test {
 		type Event = Event;
+		type OnDelegationChange = ();
 	}
 
 	pub type System = system::Module<Test>;
@@ -41,6 +41,7 @@ use runtime_primitives::traits::{Zero, Hash};
 use codec::Encode;
 
 pub use voting::voting::Tally;
+use voting::VoteStage;
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, PartialEq, Clone, Copy)]
@@ -48,6 +49,7 @@ pub enum ProposalStage {
 	PreVoting,
 	Voting,
 	Completed,
+	Cancelled,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -101,16 +103,19 @@ decl_module! {
 			ensure!(<ProposalOf<T>>::get(hash) == None, "Proposal already exists");
 
 			// create a vote to go along with the proposal
-			let vote_id = <voting::Module<T>>::create_vote(
+			let vote_id = <voting::Module<T>>::do_create_vote(
 				_sender.clone(),
 				voting::VoteType::Binary,
 				false, // not commit-reveal
 				voting::TallyType::OneCoin,
 				vec![YES_VOTE, NO_VOTE],
+				None, // advanced by this module
+				Default::default(),
 			)?;
 
 			let index = <ProposalCount<T>>::get();
 			<ProposalCount<T>>::mutate(|i| *i += 1);
+			<ProposalForVote<T>>::insert(vote_id, hash);
 			<ProposalOf<T>>::insert(hash, ProposalRecord {
 				index: index,
 				author: _sender.clone(),
@@ -167,33 +172,85 @@ decl_module! {
 			Ok(())
 		}
 
-		/// Check all active proposals to see if they're completed. If so, update
-		/// them in storage and emit an event.
+		/// Check all active proposals to see if their voting time is over. If so,
+		/// complete their votes, which marks them completed through `OnVoteCompleted`.
+		/// Proposals whose votes cannot complete yet stay active and are tried
+		/// again the next block.
 		fn on_finalise(_n: T::BlockNumber) {
-			let (finished, active): (Vec<_>, _) = <ActiveProposals<T>>::get()
+			Self::migrate_proposal_votes();
+			let now = <timestamp::Module<T>>::get();
+			let active: Vec<_> = <ActiveProposals<T>>::get()
 				.into_iter()
-				.partition(|(_, exp)| <timestamp::Module<T>>::get() > *exp);
-			
+				.filter(|(hash, exp)| {
+					if now <= *exp {
+						return true;
+					}
+					// voting -> completed, possibly after its delegators are counted
+					let vote_id = match <ProposalOf<T>>::get(hash) {
+						Some(record) => record.vote_id,
+						None => return false,
+					};
+					let _ = <voting::Module<T>>::advance_stage(vote_id);
+					<voting::Module<T>>::vote_records(vote_id).map_or(false, |record| {
+						record.data.stage != VoteStage::Completed && record.data.stage != VoteStage::Cancelled
+					})
+				})
+				.collect();
 			<ActiveProposals<T>>::put(active);
-			finished.into_iter().for_each(move |(completed_hash, _)| {
-				match <ProposalOf<T>>::get(completed_hash) {
-					Some(record) => {
-						// voting -> completed
-						let vote_id = record.vote_id;
-						// TODO: handle possible errors from advance_stage?
-						let _ = <voting::Module<T>>::advance_stage(vote_id);
-						<ProposalOf<T>>::insert(completed_hash, ProposalRecord {
-							stage: ProposalStage::Completed,
-							transition_time: T::Moment::zero(),
-							..record
-						});
-						// tally the final vote to include in completion Event
-						let final_outcome = <voting::Module<T>>::tally(vote_id);
-						Self::deposit_event(RawEvent::VotingCompleted(completed_hash, vote_id, final_outcome));
-					},
-					None => { } // TODO: emit an error here?
-				}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	// maps the votes of proposals created before votes were mapped back to
+	// their proposals, so those proposals still complete. Runs once, before
+	// the upgraded runtime first finalises a block or hears of a vote.
+	fn migrate_proposal_votes() {
+		if Self::storage_version() >= 1 {
+			return;
+		}
+
+		Self::proposals().into_iter()
+			.filter_map(|hash| <ProposalOf<T>>::get(hash).map(|record| (record.vote_id, hash)))
+			.for_each(|(vote_id, hash)| <ProposalForVote<T>>::insert(vote_id, hash));
+		<StorageVersion<T>>::put(1);
+	}
+}
+
+impl<T: Trait> voting::OnVoteCompleted for Module<T> {
+	fn on_vote_completed(vote_id: u64, _result: voting::VoteResult) {
+		Self::migrate_proposal_votes();
+		let hash = match Self::proposal_for_vote(vote_id) {
+			Some(hash) => hash,
+			None => return,
+		};
+		if let Some(record) = <ProposalOf<T>>::get(hash) {
+			<ProposalOf<T>>::insert(hash, ProposalRecord {
+				stage: ProposalStage::Completed,
+				transition_time: T::Moment::zero(),
+				..record
+			});
+			<ActiveProposals<T>>::mutate(|proposals| proposals.retain(|(h, _)| *h != hash));
+			// tally the final vote to include in completion Event
+			let final_outcome = <voting::Module<T>>::tally(vote_id);
+			Self::deposit_event(RawEvent::VotingCompleted(hash, vote_id, final_outcome));
+		}
+	}
+
+	fn on_vote_cancelled(vote_id: u64) {
+		Self::migrate_proposal_votes();
+		let hash = match Self::proposal_for_vote(vote_id) {
+			Some(hash) => hash,
+			None => return,
+		};
+		if let Some(record) = <ProposalOf<T>>::get(hash) {
+			<ProposalOf<T>>::insert(hash, ProposalRecord {
+				stage: ProposalStage::Cancelled,
+				transition_time: T::Moment::zero(),
+				..record
 			});
+			<ActiveProposals<T>>::mutate(|proposals| proposals.retain(|(h, _)| *h != hash));
+			Self::deposit_event(RawEvent::ProposalCancelled(hash, vote_id));
 		}
 	}
 }
@@ -211,6 +268,8 @@ decl_event!(
 		VotingStarted(Hash, u64, Moment),
 		/// Emitted when voting is completed: (ProposalHash, VoteId, VoteResults)
 		VotingCompleted(Hash, u64, Tally<Balance>),
+		/// Emitted when the vote of a proposal is cancelled: (ProposalHash, VoteId)
+		ProposalCancelled(Hash, u64),
 	}
 );
 
@@ -226,5 +285,9 @@ decl_storage! {
 		pub VotingTime get(voting_time) config(): T::Moment;
 		/// Map for retrieving the information about any proposal from its hash.
 		pub ProposalOf get(proposal_of): map T::Hash => Option<ProposalRecord<T::AccountId, T::Moment>>;
+		/// Map for retrieving the proposal a vote was created for.
+		pub ProposalForVote get(proposal_for_vote): map u64 => Option<T::Hash>;
+		/// The layout version of the stored proposals
+		pub StorageVersion get(storage_version): u32;
 	}
 }
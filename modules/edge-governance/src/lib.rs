@@ -58,6 +58,7 @@ mod tests {
 	use rstd::prelude::*;
 	use codec::Encode;
 	use runtime_support::dispatch::Result;
+	use runtime_support::StorageMap;
 	use system::{EventRecord, Phase};
 	use runtime_io::with_externalities;
 	use runtime_io::ed25519::Pair;
@@ -65,8 +66,8 @@ mod tests {
 	// The testing primitives are very useful for avoiding having to work with signatures
 	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are requried.
 	use runtime_primitives::{
-		BuildStorage,
-		traits::{BlakeTwo256, OnFinalise, IdentityLookup},
+		BuildStorage, Ed25519Signature,
+		traits::{BlakeTwo256, OnFinalise, IdentityLookup, EnsureOrigin},
 		testing::{Digest, DigestItem, Header, UintAuthorityId}
 	};
 	use voting::{VoteStage, VoteType};
@@ -123,10 +124,26 @@ mod tests {
 
 	impl delegation::Trait for Test {
 		type Event = Event;
+		type OnDelegationChange = Voting;
+	}
+
+	pub struct EnsureRoot;
+	impl EnsureOrigin<Origin> for EnsureRoot {
+		type Success = ();
+		fn ensure_origin(o: Origin) -> Result {
+			system::ensure_root(o)
+		}
 	}
 
 	impl voting::Trait for Test {
 		type Event = Event;
+		type CancelOrigin = EnsureRoot;
+		type Eligibility = ();
+		type OnBondSlashed = ();
+		type OnVoteCompleted = Governance;
+		type Signature = Ed25519Signature;
+		type KeyOrigin = EnsureRoot;
+		type ParameterOrigin = EnsureRoot;
 	}
 
 	impl Trait for Test {
@@ -552,6 +569,123 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn proposals_voting_before_upgrade_should_complete() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let category = governance::ProposalCategory::Signaling;
+			let (title, proposal) = generate_proposal();
+			let hash = build_proposal_hash(public, &proposal);
+			assert_ok!(propose(public, title, proposal, category));
+			assert_ok!(advance_proposal(public, hash));
+
+			// proposals created before votes were mapped back to them
+			<governance::ProposalForVote<Test>>::remove(1);
+			assert_eq!(Governance::storage_version(), 0);
+
+			Timestamp::set_timestamp(10001);
+			<Governance as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Governance::storage_version(), 1);
+			assert_eq!(Governance::proposal_for_vote(1), Some(hash));
+			assert_eq!(Governance::active_proposals(), vec![]);
+			assert_eq!(Governance::proposal_of(hash).unwrap().stage, ProposalStage::Completed);
+		});
+	}
+
+	#[test]
+	fn cancelled_proposal_vote_should_cancel_proposal() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let category = governance::ProposalCategory::Signaling;
+			let (title, proposal) = generate_proposal();
+			let hash = build_proposal_hash(public, &proposal);
+			assert_ok!(propose(public, title, proposal, category));
+			assert_ok!(Voting::cancel_vote(Origin::signed(public), 1));
+
+			assert_eq!(System::events()[3], EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::governance(RawEvent::ProposalCancelled(hash, 1))
+			});
+			assert_err!(advance_proposal(public, hash), "Proposal not in pre-voting stage");
+			assert_eq!(
+				Governance::proposal_of(hash),
+				Some(ProposalRecord {
+					stage: ProposalStage::Cancelled,
+					..make_record(public, title, proposal, category)
+				})
+			);
+		});
+	}
+
+	#[test]
+	fn force_cancelled_proposal_vote_should_stop_voting() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let category = governance::ProposalCategory::Signaling;
+			let (title, proposal) = generate_proposal();
+			let hash = build_proposal_hash(public, &proposal);
+			assert_ok!(propose(public, title, proposal, category));
+			assert_ok!(advance_proposal(public, hash));
+			assert_ok!(Voting::force_cancel_vote(system::RawOrigin::Root.into(), 1));
+
+			assert_eq!(Governance::active_proposals(), vec![]);
+			assert_eq!(
+				Governance::proposal_of(hash),
+				Some(ProposalRecord {
+					stage: ProposalStage::Cancelled,
+					..make_record(public, title, proposal, category)
+				})
+			);
+
+			// the proposal is no longer finalised once its time is up
+			Timestamp::set_timestamp(10001);
+			<Governance as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Governance::proposal_of(hash).unwrap().stage, ProposalStage::Cancelled);
+		});
+	}
+
+	#[test]
+	fn proposal_should_stay_active_while_delegators_are_counted() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Delegation graph: 2..=101 --> 1
+			 *  1 votes yes, and more delegators are left to count when the
+			 *  voting time is up than one reveal counts
+			 */
+			System::set_block_number(1);
+			let public = get_test_key();
+			let category = governance::ProposalCategory::Signaling;
+			let (title, proposal) = generate_proposal();
+			let hash = build_proposal_hash(public, &proposal);
+			assert_ok!(propose(public, title, proposal, category));
+			assert_ok!(advance_proposal(public, hash));
+
+			let voter = H256::from_low_u64_be(1);
+			for i in 2..102 {
+				assert_ok!(delegation::Module::<Test>::delegate_to(Origin::signed(H256::from_low_u64_be(i)), voter));
+			}
+			assert_ok!(Voting::reveal(Origin::signed(voter), 1, vec![governance::YES_VOTE], None, 0));
+			assert!(Voting::pending_recounts(1) > 0);
+
+			// the vote stops taking reveals but cannot complete yet
+			Timestamp::set_timestamp(10001);
+			<Governance as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Tallying);
+			assert_eq!(Governance::active_proposals(), vec![(hash, 10000)]);
+			assert_eq!(Governance::proposal_of(hash).unwrap().stage, ProposalStage::Voting);
+
+			// and completes the proposal once they are counted
+			<Voting as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::pending_recounts(1), 0);
+			<Governance as OnFinalise<u64>>::on_finalise(2);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Governance::active_proposals(), vec![]);
+			assert_eq!(Governance::proposal_of(hash).unwrap().stage, ProposalStage::Completed);
+		});
+	}
+
 	#[test]
 	fn non_author_advance_should_fail() {
 		with_externalities(&mut new_test_ext(), || {
@@ -161,6 +161,7 @@ decl_module! {
 			}
 
 			if approve {
+				<VerifiedAccounts<T>>::insert(&record.account, true);
 				<IdentityOf<T>>::insert(identity_hash, IdentityRecord {
 					stage: IdentityStage::Verified,
 					expiration_time: T::Moment::zero(),
@@ -203,6 +204,7 @@ decl_module! {
 		/// finalised. Once an identity expires, it is deleted from storage.
 		/// TODO: We may want to limit how many identities will be purged each block.
 		fn on_finalise(_n: T::BlockNumber) {
+			Self::migrate_verified_accounts();
 			let (expired, valid): (Vec<_>, _) = <IdentitiesPending<T>>::get()
 				.into_iter()
 				.partition(|(_, exp)| (<timestamp::Module<T>>::get() > *exp) && (*exp > T::Moment::zero()));
@@ -224,6 +226,21 @@ impl<T: Trait> Module<T> {
 		<IdentityOf<T>>::remove(identity_hash);
 		<IdentitiesPending<T>>::mutate(|idents| idents.retain(|(hash, _)| hash != identity_hash));
 	}
+
+	// marks the accounts of identities verified before verified accounts were
+	// tracked, so eligibility checks see them. Runs once, when the upgraded
+	// runtime first finalises a block.
+	fn migrate_verified_accounts() {
+		if Self::storage_version() >= 1 {
+			return;
+		}
+
+		Self::identities().into_iter()
+			.filter_map(|hash| Self::identity_of(hash))
+			.filter(|record| record.stage == IdentityStage::Verified)
+			.for_each(|record| <VerifiedAccounts<T>>::insert(&record.account, true));
+		<StorageVersion<T>>::put(1);
+	}
 }
 
 /// An event in this module.
@@ -256,7 +273,11 @@ decl_storage! {
 		pub ExpirationTime get(expiration_time) config(): T::Moment;
 		/// Identity types of users
 		pub UsedTypes get(used_types): map T::AccountId => Vec<IdentityType>;
+		/// Whether each account holds at least one verified identity
+		pub VerifiedAccounts get(is_verified): map T::AccountId => bool;
 		/// Verifier set
 		pub Verifiers get(verifiers) config(): Vec<T::AccountId>;
+		/// The layout version of the stored identities
+		pub StorageVersion get(storage_version): u32;
 	}
 }
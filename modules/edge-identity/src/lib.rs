@@ -60,6 +60,7 @@ mod tests {
 	use runtime_io::ed25519::Pair;
 	use runtime_io::with_externalities;
 	use runtime_support::dispatch::Result;
+	use runtime_support::{StorageMap, StorageValue};
 	use system::{EventRecord, Phase};
 	// The testing primitives are very useful for avoiding having to work with
 	// public keys. `u64` is used as the `AccountId` and no `Signature`s are requried.
@@ -460,6 +461,7 @@ mod tests {
 			);
 			assert_eq!(Identity::identities(), vec![identity_hash]);
 			assert_eq!(Identity::identities_pending(), vec![]);
+			assert!(Identity::is_verified(public));
 			assert_eq!(
 				Identity::identity_of(identity_hash),
 				Some(IdentityRecord {
@@ -472,6 +474,32 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn verified_identities_should_be_backfilled() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = H256::from_low_u64_be(2);
+			let other = H256::from_low_u64_be(3);
+			let verified_hash = build_identity_hash(b"github", b"drewstone");
+			let pending_hash = build_identity_hash(b"github", b"jake");
+
+			// records verified before verified accounts were tracked
+			<identity::Identities<Test>>::put(vec![verified_hash, pending_hash]);
+			<identity::IdentityOf<Test>>::insert(verified_hash, IdentityRecord {
+				stage: IdentityStage::Verified,
+				expiration_time: 0,
+				..default_identity_record(public, b"github", b"drewstone")
+			});
+			<identity::IdentityOf<Test>>::insert(pending_hash, default_identity_record(other, b"github", b"jake"));
+			assert!(!Identity::is_verified(public));
+
+			<Identity as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Identity::storage_version(), 1);
+			assert!(Identity::is_verified(public));
+			assert!(!Identity::is_verified(other));
+		});
+	}
+
 	#[test]
 	fn attest_after_verify_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
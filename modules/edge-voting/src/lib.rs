@@ -27,6 +27,9 @@ extern crate serde_derive;
 #[cfg(test)]
 #[macro_use]
 extern crate hex_literal;
+#[cfg(test)]
+#[macro_use]
+extern crate proptest;
 #[macro_use] extern crate parity_codec_derive;
 #[macro_use] extern crate srml_support;
 
@@ -41,11 +44,23 @@ extern crate sr_io as runtime_io;
 
 extern crate srml_balances as balances;
 extern crate srml_system as system;
+extern crate srml_timestamp as timestamp;
+extern crate srml_consensus as consensus;
 extern crate edge_delegation as delegation;
+extern crate curve25519_dalek;
+extern crate sha2;
 
+pub mod ring;
+pub mod threshold;
 pub mod voting;
 pub use voting::{Module, Trait, RawEvent, Event};
-pub use voting::{VoteStage, VoteType, TallyType, VoteRecord, VoteData};
+pub use voting::{VoteStage, VoteType, TallyType, VoteRecord, VoteData, Ballot, MerkleProof, VoteSchedule};
+pub use voting::{Quorum, Threshold, VoteRules, VoteResult, VoterPolicy, VoterEligibility, OnBondSlashed, PrunedVote, PruneCursor, PruneStep};
+pub use voting::{OnVoteCompleted, CommitteeResult, RunoffRound, commitment_preimage, scalar_outcome, scalar_value};
+pub use voting::{SignedBallot, signed_ballot_payload};
+pub use threshold::{ThresholdKey, Ciphertext, DecryptionShare};
+#[cfg(feature = "std")]
+pub use voting::compute_commitment;
 
 // Tests for Delegation Module
 #[cfg(test)]
@@ -58,11 +73,15 @@ mod tests {
 	use runtime_io::with_externalities;
 	use primitives::{H256, Blake2Hasher};
 	use rstd::result;
+	use codec::Encode;
+	use balances::EnsureAccountLiquid;
+	use runtime_support::{StorageMap, StorageValue};
+	use runtime_primitives::transaction_validity::TransactionValidity;
 	// The testing primitives are very useful for avoiding having to work with signatures
 	// or public keys. `u64` is used as the `AccountId` and no `Signature`s are requried.
 	use runtime_primitives::{
-		BuildStorage, traits::{BlakeTwo256, Hash, IdentityLookup},
-		testing::{Digest, DigestItem, Header}
+		BuildStorage, Ed25519Signature, traits::{BlakeTwo256, Hash, OnFinalise, IdentityLookup, EnsureOrigin},
+		testing::{Digest, DigestItem, Header, UintAuthorityId}
 	};
 
 	static SECRET: [u8; 32] = [1,0,1,0,1,0,1,0,1,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,4];
@@ -100,23 +119,76 @@ mod tests {
 		type Log = DigestItem;
 	}
 
+	impl consensus::Trait for Test {
+		type Log = DigestItem;
+		type SessionKey = UintAuthorityId;
+		type InherentOfflineReport = ();
+	}
+
+	impl timestamp::Trait for Test {
+		type Moment = u64;
+		type OnTimestampSet = ();
+	}
+
 	impl balances::Trait for Test {
 		type Balance = u64;
 		type OnFreeBalanceZero = ();
 		type OnNewAccount = ();
-		type EnsureAccountLiquid = ();
+		type EnsureAccountLiquid = Voting;
 		type Event = Event;
 	}
 
 	impl delegation::Trait for Test {
 		type Event = Event;
+		type OnDelegationChange = Voting;
+	}
+
+	pub struct EnsureRoot;
+	impl EnsureOrigin<Origin> for EnsureRoot {
+		type Success = ();
+		fn ensure_origin(o: Origin) -> Result {
+			system::ensure_root(o)
+		}
+	}
+
+	// approves accounts with an even low byte
+	pub struct EvenAccounts;
+	impl VoterEligibility<H256> for EvenAccounts {
+		fn can_vote(_vote_id: u64, who: &H256) -> bool {
+			who.to_low_u64_be() % 2 == 0
+		}
+	}
+
+	thread_local! {
+		static COMPLETED: ::std::cell::RefCell<Vec<(u64, VoteResult)>> = ::std::cell::RefCell::new(vec![]);
+		static CANCELLED: ::std::cell::RefCell<Vec<u64>> = ::std::cell::RefCell::new(vec![]);
+	}
+
+	// records the votes that completed or were cancelled, in order
+	pub struct CompletedVotes;
+	impl OnVoteCompleted for CompletedVotes {
+		fn on_vote_completed(vote_id: u64, result: VoteResult) {
+			COMPLETED.with(|c| c.borrow_mut().push((vote_id, result)));
+		}
+		fn on_vote_cancelled(vote_id: u64) {
+			CANCELLED.with(|c| c.borrow_mut().push(vote_id));
+		}
 	}
 
 	impl Trait for Test {
 		type Event = Event;
+		type CancelOrigin = EnsureRoot;
+		type Eligibility = EvenAccounts;
+		type OnBondSlashed = ();
+		type OnVoteCompleted = CompletedVotes;
+		type Signature = Ed25519Signature;
+		type KeyOrigin = EnsureRoot;
+		type ParameterOrigin = EnsureRoot;
 	}
 
 	pub type System = system::Module<Test>;
+	pub type Timestamp = timestamp::Module<Test>;
+	pub type Balances = balances::Module<Test>;
 	pub type Delegation = delegation::Module<Test>;
 	pub type Voting = Module<Test>;
 
@@ -130,6 +202,17 @@ mod tests {
 				_genesis_phantom_data: Default::default(),
 			}.build_storage().unwrap().0,
 		);
+		t.extend(
+			voting::GenesisConfig::<Test> {
+				lock_period: 10,
+				max_lock_periods: 6,
+				commit_bond: 0,
+				creation_deposit: 0,
+				max_outcomes: 16,
+				retention_period: 0,
+				decryption_period: 10,
+			}.build_storage().unwrap().0,
+		);
 		// We use default for brevity, but you can configure as desired if needed.
 		t.into()
 	}
@@ -141,29 +224,124 @@ mod tests {
 		tally_type: voting::TallyType,
 		outcomes: &[[u8; 32]]
 	) -> result::Result<u64, &'static str> {
-		Voting::create_vote(who,
+		Voting::do_create_vote(who,
+							vote_type,
+							is_commit_reveal,
+							tally_type,
+							outcomes.to_vec(),
+							None,
+							Default::default())
+	}
+
+	fn create_scheduled_vote(
+		who: H256,
+		vote_type: voting::VoteType,
+		is_commit_reveal: bool,
+		tally_type: voting::TallyType,
+		outcomes: &[[u8; 32]],
+		schedule: VoteSchedule<u64, u64>
+	) -> result::Result<u64, &'static str> {
+		Voting::do_create_vote(who,
 							vote_type,
 							is_commit_reveal,
 							tally_type,
-							outcomes.to_vec())
+							outcomes.to_vec(),
+							Some(schedule),
+							Default::default())
+	}
+
+	fn create_vote_with_rules(
+		who: H256,
+		vote_type: voting::VoteType,
+		tally_type: voting::TallyType,
+		outcomes: &[[u8; 32]],
+		rules: VoteRules<u64>
+	) -> result::Result<u64, &'static str> {
+		Voting::do_create_vote(who, vote_type, false, tally_type, outcomes.to_vec(), None, rules)
+	}
+
+	fn create_vote_as_signed(who: H256, vote_type: voting::VoteType, outcomes: Vec<[u8; 32]>) -> Result {
+		Voting::create_vote(Origin::signed(who), vote_type, false, TallyType::OnePerson, outcomes, None, Default::default())
 	}
 
 	fn commit(who: H256, vote_id: u64, commit: [u8; 32]) -> Result {
 		Voting::commit(Origin::signed(who), vote_id, commit)
 	}
 
-	fn reveal(who: H256, vote_id: u64, vote: [u8; 32], secret: Option<[u8; 32]>) -> Result {
-		Voting::reveal(Origin::signed(who), vote_id, vote, secret)
+	fn reveal(who: H256, vote_id: u64, vote: Vec<[u8; 32]>, secret: Option<[u8; 32]>) -> Result {
+		Voting::reveal(Origin::signed(who), vote_id, vote, secret, 0)
+	}
+
+	fn reveal_with_conviction(who: H256, vote_id: u64, vote: Vec<[u8; 32]>, conviction: u8) -> Result {
+		Voting::reveal(Origin::signed(who), vote_id, vote, None, conviction)
+	}
+
+	fn reveal_merkle(vote_id: u64, vote: Vec<[u8; 32]>, secret: &[u8; 32], proof: MerkleProof) -> Result {
+		let signature = sign_merkle_ballot(secret, vote_id, &vote);
+		Voting::reveal_merkle(system::RawOrigin::Inherent.into(), vote_id, vote, ring::public_key(secret), proof, signature)
+	}
+
+	fn reveal_anonymous(vote_id: u64, vote: Vec<[u8; 32]>, signature: ring::RingSignature) -> Result {
+		Voting::reveal_anonymous(system::RawOrigin::Inherent.into(), vote_id, vote, signature)
+	}
+
+	fn allow_voters(who: H256, vote_id: u64, voters: Vec<H256>) -> Result {
+		Voting::allow_voters(Origin::signed(who), vote_id, voters)
+	}
+
+	fn cancel_vote(who: H256, vote_id: u64) -> Result {
+		Voting::cancel_vote(Origin::signed(who), vote_id)
+	}
+
+	fn force_cancel_vote(vote_id: u64) -> Result {
+		Voting::force_cancel_vote(system::RawOrigin::Root.into(), vote_id)
 	}
 
 	fn advance_stage_as_initiator(who: H256, vote_id: u64) -> Result {
 		Voting::advance_stage_as_initiator(Origin::signed(who), vote_id)
 	}
 
+	fn sign_relayed_ballot(pair: &Pair, vote_id: u64, ballot: Vec<[u8; 32]>, nonce: u64) -> SignedBallot<H256, Ed25519Signature> {
+		let payload = signed_ballot_payload(&System::block_hash(0), vote_id, &ballot, nonce);
+		SignedBallot {
+			signer: pair.public().0.into(),
+			vote_id: vote_id,
+			ballot: ballot,
+			nonce: nonce,
+			signature: Ed25519Signature(pair.sign(&payload).0.into()),
+		}
+	}
+
+	fn submit_signed_ballots(who: H256, ballots: Vec<SignedBallot<H256, Ed25519Signature>>) -> Result {
+		Voting::submit_signed_ballots(Origin::signed(who), ballots)
+	}
+
+	fn create_encrypted_vote(who: H256, outcomes: &[[u8; 32]]) -> result::Result<u64, &'static str> {
+		Voting::create_encrypted_vote(who, VoteType::Binary, TallyType::OnePerson, outcomes.to_vec(), None, Default::default())
+	}
+
+	fn cast_encrypted(who: H256, vote_id: u64, key: &ThresholdKey, nonce: u8, vote: Vec<[u8; 32]>) -> Result {
+		let ciphertext = threshold::encrypt(key, &[nonce; 32], &(vote_id, who).encode(), &vote.encode()).unwrap();
+		Voting::cast_encrypted(Origin::signed(who), vote_id, ciphertext)
+	}
+
+	fn decryption_shares(vote_id: u64, index: u32, secret: &[u8; 32], voters: &[H256]) -> Vec<(H256, DecryptionShare)> {
+		voters.iter()
+			.map(|who| {
+				let ciphertext = Voting::ciphertext_of((vote_id, *who)).unwrap();
+				(*who, threshold::decryption_share(index, secret, &ciphertext.ephemeral).unwrap())
+			})
+			.collect()
+	}
+
 	fn delegate_to(who: H256, to: H256) -> Result {
 		Delegation::delegate_to(Origin::signed(who), to)
 	}
 
+	fn undelegate_from(who: H256, from: H256) -> Result {
+		Delegation::undelegate_from(Origin::signed(who), from)
+	}
+
 	fn get_test_key() -> H256 {
 		let pair: Pair = Pair::from_seed(&hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"));
 		let public: H256 = pair.public().0.into();
@@ -213,6 +391,48 @@ mod tests {
 		]);
 	}
 
+	fn generate_ring() -> (Vec<[u8; 32]>, Vec<[u8; 32]>) {
+		let secrets: Vec<[u8; 32]> = (1..4).map(|i| [i; 32]).collect();
+		let ring = secrets.iter().map(|s| ring::public_key(s)).collect();
+		return (secrets, ring);
+	}
+
+	fn sign_ballot(ring: &[[u8; 32]], index: usize, secret: &[u8; 32], vote_id: u64, vote: &Vec<[u8; 32]>) -> ring::RingSignature {
+		let message = (vote_id, vote).encode();
+		ring::sign(ring, index, secret, &vote_id.encode(), &message).unwrap()
+	}
+
+	fn sign_merkle_ballot(secret: &[u8; 32], vote_id: u64, vote: &Vec<[u8; 32]>) -> ring::RingSignature {
+		sign_ballot(&[ring::public_key(secret)], 0, secret, vote_id, vote)
+	}
+
+	fn hash(data: &[u8]) -> [u8; 32] {
+		BlakeTwo256::hash(data).into()
+	}
+
+	// builds a four leaf commitment tree, returning the nullifier secrets and
+	// nullifiers, the root and the membership proof of each leaf
+	fn generate_merkle_tree() -> (Vec<[u8; 32]>, Vec<[u8; 32]>, [u8; 32], Vec<MerkleProof>) {
+		let secrets: Vec<[u8; 32]> = (1..5).map(|i| [i + 10; 32]).collect();
+		let nullifiers: Vec<[u8; 32]> = secrets.iter().map(|s| ring::public_key(s)).collect();
+		let leaves: Vec<[u8; 32]> = nullifiers.iter().map(|n| hash(n)).collect();
+		let pair = |a: &[u8; 32], b: &[u8; 32]| {
+			let mut buf = a.to_vec();
+			buf.extend_from_slice(b);
+			hash(&buf)
+		};
+		let left = pair(&leaves[0], &leaves[1]);
+		let right = pair(&leaves[2], &leaves[3]);
+		let root = pair(&left, &right);
+		let proofs = vec![
+			MerkleProof { index: 0, path: vec![leaves[1], right] },
+			MerkleProof { index: 1, path: vec![leaves[0], right] },
+			MerkleProof { index: 2, path: vec![leaves[3], left] },
+			MerkleProof { index: 3, path: vec![leaves[2], left] },
+		];
+		return (secrets, nullifiers, root, proofs);
+	}
+
 	fn make_record(
 		id: u64,
 		author: H256,
@@ -221,11 +441,9 @@ mod tests {
 		tally_type: voting::TallyType,
 		outcomes: &[[u8; 32]],
 		stage: VoteStage
-	) -> VoteRecord<H256> {
+	) -> VoteRecord<H256, u64> {
 		VoteRecord {
 			id: id,
-			commitments: vec![],
-			reveals: vec![],
 			outcomes: outcomes.to_vec(),
 			data: VoteData {
 				initiator: author,
@@ -233,6 +451,8 @@ mod tests {
 				vote_type: vote_type,
 				tally_type: tally_type,
 				is_commit_reveal: is_commit_reveal,
+				merkle_root: None,
+				rules: Default::default(),
 			},
 		}
 	}
@@ -312,15 +532,14 @@ mod tests {
 		});
 	}
 
-	// TODO: Ensure we fix this test when we support these types!
 	#[test]
-	fn create_vote_with_unsupported_type_should_not_work() {
+	fn create_anonymous_vote_without_setup_should_not_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let public = get_test_key();
 			let vote = generate_1p1v_public_multi_vote();
 			let outcome: [u8; 32] = [0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,4];
-			assert_err!(create_vote(public, VoteType::AnonymousRing, vote.1, vote.2, &[outcome]), "Unsupported vote type");
+			assert_err!(create_vote(public, VoteType::MerkleMembership, vote.1, vote.2, &[outcome]), "Merkle votes must be created with a merkle root");
 			assert_eq!(Voting::vote_record_count(), 0);
 			assert_eq!(Voting::vote_records(1), None);
 		});
@@ -354,7 +573,7 @@ mod tests {
 			System::set_block_number(1);
 			let public = get_test_key();
 			let commit_value = SECRET;
-			assert_err!(reveal(public, 1, commit_value, Some(commit_value)), "Vote record does not exist");
+			assert_err!(reveal(public, 1, vec![commit_value], Some(commit_value)), "Vote record does not exist");
 		});
 	}
 
@@ -366,7 +585,7 @@ mod tests {
 			let vote = generate_1p1v_public_binary_vote();
 			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
 			let vote_outcome = vote.3[0];
-			assert_err!(reveal(public, 1, vote_outcome, Some(vote_outcome)), "Vote is not in voting stage");
+			assert_err!(reveal(public, 1, vec![vote_outcome], Some(vote_outcome)), "Vote is not in voting stage");
 		});
 	}
 
@@ -416,11 +635,9 @@ mod tests {
 			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
 			assert_ok!(advance_stage_as_initiator(public, 1));
 			let public2 = get_test_key_2();
-			assert_ok!(reveal(public2, 1, vote.3[0], Some(vote.3[0])));
-			assert_eq!(
-				Voting::vote_records(1).unwrap().reveals,
-				vec![(public2, vote.3[0])]
-			);
+			assert_ok!(reveal(public2, 1, vec![vote.3[0]], Some(vote.3[0])));
+			assert_eq!(Voting::reveal_of((1, public2)), Some(vec![vote.3[0]]));
+			assert_eq!(Voting::voters_of(1), vec![public2]);
 			assert_eq!(System::events(), vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
@@ -432,7 +649,7 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
-					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vote.3[0]))
+					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vec![vote.3[0]]))
 				}
 			]);
 		});
@@ -447,7 +664,7 @@ mod tests {
 			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
 			assert_ok!(advance_stage_as_initiator(public, 1));
 			let public2 = get_test_key_2();
-			assert_ok!(reveal(public2, 1, vote.3[0], Some(vote.3[0])));
+			assert_ok!(reveal(public2, 1, vec![vote.3[0]], Some(vote.3[0])));
 			assert_ok!(advance_stage_as_initiator(public, 1));
 			assert_eq!(
 				Voting::vote_records(1).unwrap().data.stage,
@@ -464,7 +681,7 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
-					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vote.3[0]))
+					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vec![vote.3[0]]))
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
@@ -515,7 +732,7 @@ mod tests {
 				true
 			);
 			let public2 = get_test_key_2();
-			assert_err!(reveal(public2, 1, vote.3[0], Some(vote.3[0])), "Vote is not in voting stage");
+			assert_err!(reveal(public2, 1, vec![vote.3[0]], Some(vote.3[0])), "Vote is not in voting stage");
 		});
 	}
 
@@ -535,12 +752,10 @@ mod tests {
 			buf.extend_from_slice(&vote.3[0]);
 			let commit_hash: [u8; 32] = BlakeTwo256::hash_of(&buf).into();
 			assert_ok!(commit(public2, 1, commit_hash));
-			assert_eq!(
-				Voting::vote_records(1).unwrap().commitments,
-				vec![(public2, commit_hash)]
-			);
+			assert_eq!(Voting::commitment_of((1, public2)), Some(commit_hash));
+			assert_eq!(Voting::committers_of(1), vec![public2]);
 
-			assert_err!(reveal(public2, 1, vote.3[0], Some(secret)), "Vote is not in voting stage");
+			assert_err!(reveal(public2, 1, vec![vote.3[0]], Some(secret)), "Vote is not in voting stage");
 		});
 	}
 
@@ -560,13 +775,11 @@ mod tests {
 			buf.extend_from_slice(&vote.3[0]);
 			let commit_hash: [u8; 32] = BlakeTwo256::hash_of(&buf).into();
 			assert_ok!(commit(public2, 1, commit_hash));
-			assert_eq!(
-				Voting::vote_records(1).unwrap().commitments,
-				vec![(public2, commit_hash)]
-			);
+			assert_eq!(Voting::commitment_of((1, public2)), Some(commit_hash));
+			assert_eq!(Voting::committers_of(1), vec![public2]);
 
 			assert_ok!(advance_stage_as_initiator(public, 1));
-			assert_ok!(reveal(public2, 1, vote.3[0], Some(secret)));
+			assert_ok!(reveal(public2, 1, vec![vote.3[0]], Some(secret)));
 			assert_eq!(System::events(), vec![
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
@@ -586,7 +799,7 @@ mod tests {
 				},
 				EventRecord {
 					phase: Phase::ApplyExtrinsic(0),
-					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vote.3[0]))
+					event: Event::voting(voting::RawEvent::VoteRevealed(1, public2, vec![vote.3[0]]))
 				}
 			]);
 		});
@@ -600,7 +813,7 @@ mod tests {
 			let vote = generate_1p1v_public_binary_vote();
 			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
 			assert_ok!(advance_stage_as_initiator(public, 1));
-			assert_ok!(reveal(public, 1, vote.3[0], Some(vote.3[0])));
+			assert_ok!(reveal(public, 1, vec![vote.3[0]], Some(vote.3[0])));
 			assert_ok!(advance_stage_as_initiator(public, 1));
 			assert_eq!(
 				Voting::tally(1).unwrap(),
@@ -625,7 +838,7 @@ mod tests {
 			System::set_block_number(1);
 			// set up delegations
 			let users : Vec<H256> = (0..7).map(|v| H256::from_low_u64_be(v)).collect();
-			assert_ok!(delegate_to(users[1], users[2]));
+			assert_ok!(delegate_to(H256::from_low_u64_be(500), H256::from_low_u64_be(501)));
 			assert_ok!(delegate_to(users[2], users[3]));
 			assert_ok!(delegate_to(users[3], users[4]));
 			assert_ok!(delegate_to(users[5], users[3]));
@@ -637,9 +850,9 @@ mod tests {
 			assert_ok!(advance_stage_as_initiator(creator, 1));
 
 			// perform votes
-			assert_ok!(reveal(users[2], 1, vote.3[0], None));
-			assert_ok!(reveal(users[4], 1, vote.3[1], None));
-			assert_ok!(reveal(users[5], 1, vote.3[0], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[4], 1, vec![vote.3[1]], None));
+			assert_ok!(reveal(users[5], 1, vec![vote.3[0]], None));
 			assert_ok!(advance_stage_as_initiator(creator, 1));
 			assert_eq!(
 				Voting::tally(1).unwrap(),
@@ -647,4 +860,1898 @@ mod tests {
 			);
 		});
 	}
+
+	#[test]
+	fn ranked_choice_tally_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Ballots (first preference first):
+			 *    1: [A, B]   2: [A]   3: [B, A]   4: [C, B]   5: [C]
+			 *  Round 1: A 2, B 1, C 2, D 0 -> B and D eliminated, 3 moves to A
+			 *  Round 2: A 3, C 2           -> A holds a majority
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..6).map(|v| H256::from_low_u64_be(v)).collect();
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, b, c, d) = (vote.3[0], vote.3[1], vote.3[2], vote.3[3]);
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(reveal(users[1], 1, vec![a, b], None));
+			assert_ok!(reveal(users[2], 1, vec![a], None));
+			assert_ok!(reveal(users[3], 1, vec![b, a], None));
+			assert_ok!(reveal(users[4], 1, vec![c, b], None));
+			assert_ok!(reveal(users[5], 1, vec![c], None));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(a, 3), (b, 0), (c, 2), (d, 0)]
+			);
+
+			assert_eq!(Voting::runoff_rounds(1), None);
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::runoff_rounds(1), Some(vec![
+				RunoffRound { counts: vec![(a, 2), (b, 1), (c, 2), (d, 0)], eliminated: vec![b, d] },
+				RunoffRound { counts: vec![(a, 3), (b, 0), (c, 2), (d, 0)], eliminated: vec![] },
+			]));
+		});
+	}
+
+	#[test]
+	fn ranked_choice_delegation_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Delegation graph: 1 --> 2, 3 --> 4
+			 *  Ballots: 2: [A, B], 4: [B], 5: [C, B]
+			 *  Round 1: A 2, B 2, C 1, D 0 -> C and D eliminated, 5 moves to B
+			 *  Round 2: A 2, B 3           -> B holds a majority
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..6).map(|v| H256::from_low_u64_be(v)).collect();
+			assert_ok!(delegate_to(H256::from_low_u64_be(500), H256::from_low_u64_be(501)));
+			assert_ok!(delegate_to(users[3], users[4]));
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, b, c, d) = (vote.3[0], vote.3[1], vote.3[2], vote.3[3]);
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(reveal(users[2], 1, vec![a, b], None));
+			assert_ok!(reveal(users[4], 1, vec![b], None));
+			assert_ok!(reveal(users[5], 1, vec![c, b], None));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(a, 2), (b, 3), (c, 0), (d, 0)]
+			);
+		});
+	}
+
+	#[test]
+	fn invalid_ranked_ballots_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let binary_vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_eq!(Ok(2), create_vote(public, binary_vote.0, binary_vote.1, binary_vote.2, &binary_vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(advance_stage_as_initiator(public, 2));
+
+			assert_err!(reveal(public, 1, vec![], None), "Ballot must not be empty");
+			assert_err!(reveal(public, 1, vec![vote.3[0], SECRET], None), "Invalid vote outcome");
+			assert_err!(reveal(public, 1, vec![vote.3[0], vote.3[1], vote.3[0]], None), "Ranked ballot contains duplicate outcomes");
+			assert_err!(reveal(public, 2, binary_vote.3.to_vec(), None), "Vote must select a single outcome");
+		});
+	}
+
+	#[test]
+	fn create_ring_vote_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (_, ring) = generate_ring();
+			assert_err!(create_vote(public, VoteType::AnonymousRing, false, vote.2, &vote.3), "Anonymous ring votes must be created with a ring");
+			assert_err!(Voting::create_ring_vote(public, vote.3.to_vec(), vec![ring[0]], None, Default::default()), "Ring must contain at least two keys");
+			assert_err!(Voting::create_ring_vote(public, vote.3.to_vec(), vec![ring[0], ring[0]], None, Default::default()), "Ring contains duplicate keys");
+			assert_eq!(Ok(1), Voting::create_ring_vote(public, vote.3.to_vec(), ring.clone(), None, Default::default()));
+			assert_eq!(Voting::vote_ring(1), ring);
+			assert_eq!(Voting::vote_records(1).unwrap().data.vote_type, VoteType::AnonymousRing);
+		});
+	}
+
+	#[test]
+	fn ring_signed_reveal_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, ring) = generate_ring();
+			assert_eq!(Ok(1), Voting::create_ring_vote(public, vote.3.to_vec(), ring.clone(), None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let yes = vec![vote.3[0]];
+			let no = vec![vote.3[1]];
+			let first = sign_ballot(&ring, 0, &secrets[0], 1, &yes);
+			assert_ok!(reveal_anonymous(1, yes.clone(), first.clone()));
+			assert_ok!(reveal_anonymous(1, yes.clone(), sign_ballot(&ring, 1, &secrets[1], 1, &yes)));
+			assert_ok!(reveal_anonymous(1, no.clone(), sign_ballot(&ring, 2, &secrets[2], 1, &no)));
+			assert_eq!(Voting::anonymous_reveals(1)[0], (first.key_image, yes.clone()));
+			assert_err!(reveal(public, 1, yes.clone(), None), "Anonymous ring votes must be revealed with a ring signature");
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 2), (vote.3[1], 1)]
+			);
+		});
+	}
+
+	#[test]
+	fn ring_signed_double_vote_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, ring) = generate_ring();
+			assert_eq!(Ok(1), Voting::create_ring_vote(public, vote.3.to_vec(), ring.clone(), None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let yes = vec![vote.3[0]];
+			let no = vec![vote.3[1]];
+			assert_ok!(reveal_anonymous(1, yes.clone(), sign_ballot(&ring, 0, &secrets[0], 1, &yes)));
+			// the same key yields the same key image for any ballot in this vote
+			assert_err!(
+				reveal_anonymous(1, no.clone(), sign_ballot(&ring, 0, &secrets[0], 1, &no)),
+				"Duplicate votes are not allowed"
+			);
+			assert_eq!(Voting::anonymous_reveals(1).len(), 1);
+		});
+	}
+
+	#[test]
+	fn ring_signature_from_outside_ring_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, ring) = generate_ring();
+			assert_eq!(Ok(1), Voting::create_ring_vote(public, vote.3.to_vec(), ring[..2].to_vec(), None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let yes = vec![vote.3[0]];
+			let outsider = sign_ballot(&ring, 2, &secrets[2], 1, &yes);
+			assert_err!(reveal_anonymous(1, yes.clone(), outsider), "Invalid ring signature");
+			// a valid signature does not authorise a different ballot
+			let no = vec![vote.3[1]];
+			assert_err!(
+				reveal_anonymous(1, no, sign_ballot(&ring[..2], 0, &secrets[0], 1, &yes)),
+				"Invalid ring signature"
+			);
+			assert_err!(reveal_anonymous(1, yes.clone(), sign_ballot(&ring[..2], 0, &secrets[0], 2, &yes)), "Invalid ring signature");
+		});
+	}
+
+	#[test]
+	fn unsigned_ring_reveals_should_be_validated_for_the_pool() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, ring) = generate_ring();
+			assert_eq!(Ok(1), Voting::create_ring_vote(public, vote.3.to_vec(), ring.clone(), None, Default::default()));
+
+			let provides = |call: voting::Call<Test>| match Voting::validate_unsigned(&call) {
+				TransactionValidity::Valid { provides, .. } => Some(provides),
+				_ => None,
+			};
+			let yes = vec![vote.3[0]];
+			let signature = sign_ballot(&ring, 0, &secrets[0], 1, &yes);
+			let call = || voting::Call::reveal_anonymous(1, yes.clone(), signature.clone());
+			// reveals are only gossiped while they would be accepted
+			assert_eq!(provides(call()), None);
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert!(provides(call()).is_some());
+			assert_eq!(provides(voting::Call::reveal_anonymous(1, vec![vote.3[1]], signature.clone())), None);
+			assert_eq!(provides(voting::Call::advance_stage_as_initiator(1)), None);
+
+			// ballots signed with the same key provide the same tag
+			let no = vec![vote.3[1]];
+			assert_eq!(provides(call()), provides(voting::Call::reveal_anonymous(1, no.clone(), sign_ballot(&ring, 0, &secrets[0], 1, &no))));
+			assert!(provides(call()) != provides(voting::Call::reveal_anonymous(1, yes.clone(), sign_ballot(&ring, 1, &secrets[1], 1, &yes))));
+			assert_ok!(reveal_anonymous(1, yes.clone(), signature.clone()));
+			assert_eq!(provides(call()), None);
+		});
+	}
+
+	#[test]
+	fn merkle_reveal_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, nullifiers, root, proofs) = generate_merkle_tree();
+			assert_eq!(Ok(1), Voting::create_merkle_vote(public, vote.3.to_vec(), root, None, Default::default()));
+			assert_eq!(Voting::vote_records(1).unwrap().data.merkle_root, Some(root));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let yes = vec![vote.3[0]];
+			let no = vec![vote.3[1]];
+			assert_ok!(reveal_merkle(1, yes.clone(), &secrets[0], proofs[0].clone()));
+			assert_ok!(reveal_merkle(1, no.clone(), &secrets[2], proofs[2].clone()));
+			assert_ok!(reveal_merkle(1, no.clone(), &secrets[3], proofs[3].clone()));
+			assert_err!(reveal(public, 1, yes.clone(), None), "Merkle votes must be revealed with a membership proof");
+			assert_eq!(Voting::nullifier_used((1, nullifiers[0])), true);
+			assert_eq!(Voting::nullifier_used((1, nullifiers[1])), false);
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 1), (vote.3[1], 2)]
+			);
+		});
+	}
+
+	#[test]
+	fn merkle_double_vote_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, nullifiers, root, proofs) = generate_merkle_tree();
+			assert_eq!(Ok(1), Voting::create_merkle_vote(public, vote.3.to_vec(), root, None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			assert_ok!(reveal_merkle(1, vec![vote.3[0]], &secrets[1], proofs[1].clone()));
+			assert_err!(
+				reveal_merkle(1, vec![vote.3[1]], &secrets[1], proofs[1].clone()),
+				"Duplicate votes are not allowed"
+			);
+			assert_eq!(Voting::anonymous_reveals(1), vec![(nullifiers[1], vec![vote.3[0]])]);
+		});
+	}
+
+	#[test]
+	fn merkle_reveal_with_invalid_proof_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, _, root, proofs) = generate_merkle_tree();
+			assert_eq!(Ok(1), Voting::create_merkle_vote(public, vote.3.to_vec(), root, None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let yes = vec![vote.3[0]];
+			// a proof for another leaf, an unknown nullifier and an out of range index
+			assert_err!(reveal_merkle(1, yes.clone(), &secrets[0], proofs[1].clone()), "Invalid membership proof");
+			assert_err!(reveal_merkle(1, yes.clone(), &SECRET, proofs[0].clone()), "Invalid membership proof");
+			let mut proof = proofs[0].clone();
+			proof.index = 4;
+			assert_err!(reveal_merkle(1, yes.clone(), &secrets[0], proof), "Invalid membership proof");
+			assert_eq!(Voting::anonymous_reveals(1), vec![]);
+		});
+	}
+
+	#[test]
+	fn merkle_reveal_should_be_bound_to_its_ballot() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, nullifiers, root, proofs) = generate_merkle_tree();
+			assert_eq!(Ok(1), Voting::create_merkle_vote(public, vote.3.to_vec(), root, None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			// a pending reveal cannot be replayed with another ballot or by another leaf
+			let yes = vec![vote.3[0]];
+			let no = vec![vote.3[1]];
+			let signature = sign_merkle_ballot(&secrets[0], 1, &yes);
+			let reveal = |vote: Vec<[u8; 32]>, nullifier: [u8; 32], proof: MerkleProof, signature: ring::RingSignature|
+				Voting::reveal_merkle(system::RawOrigin::Inherent.into(), 1, vote, nullifier, proof, signature);
+			assert_err!(reveal(no.clone(), nullifiers[0], proofs[0].clone(), signature.clone()), "Invalid ballot signature");
+			assert_err!(reveal(yes.clone(), nullifiers[1], proofs[1].clone(), signature.clone()), "Invalid ballot signature");
+			assert_ok!(reveal(yes.clone(), nullifiers[0], proofs[0].clone(), signature));
+			assert_eq!(Voting::anonymous_reveals(1), vec![(nullifiers[0], yes)]);
+		});
+	}
+
+	#[test]
+	fn unsigned_merkle_reveals_should_be_validated_for_the_pool() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let (secrets, nullifiers, root, proofs) = generate_merkle_tree();
+			assert_eq!(Ok(1), Voting::create_merkle_vote(public, vote.3.to_vec(), root, None, Default::default()));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let is_valid = |vote: Vec<[u8; 32]>, signature: ring::RingSignature| {
+				let call = voting::Call::reveal_merkle(1, vote, nullifiers[0], proofs[0].clone(), signature);
+				match Voting::validate_unsigned(&call) {
+					TransactionValidity::Valid { .. } => true,
+					_ => false,
+				}
+			};
+			let yes = vec![vote.3[0]];
+			assert!(is_valid(yes.clone(), sign_merkle_ballot(&secrets[0], 1, &yes)));
+			assert!(!is_valid(vec![vote.3[1]], sign_merkle_ballot(&secrets[0], 1, &yes)));
+			assert_ok!(reveal_merkle(1, yes.clone(), &secrets[0], proofs[0].clone()));
+			assert!(!is_valid(yes.clone(), sign_merkle_ballot(&secrets[0], 1, &yes)));
+		});
+	}
+
+	#[test]
+	fn change_reveal_during_voting_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let public2 = get_test_key_2();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(reveal(public2, 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(public2, 1, vec![vote.3[1]], None));
+			assert_eq!(Voting::reveal_of((1, public2)), Some(vec![vote.3[1]]));
+			assert_eq!(Voting::reveal_count(1), 1);
+			assert_eq!(System::events()[2], EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::VoteChanged(1, public2, vec![vote.3[0]], vec![vote.3[1]]))
+			});
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 0), (vote.3[1], 1)]
+			);
+
+			// once voting has ended the ballot is final
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(reveal(public2, 1, vec![vote.3[0]], None), "Vote is not in voting stage");
+		});
+	}
+
+	#[test]
+	fn change_commit_during_commit_stage_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			let public2 = get_test_key_2();
+			let commit_hash = |outcome: [u8; 32]| -> [u8; 32] {
+				compute_commitment::<_, BlakeTwo256>(&public2, &SECRET, &[outcome]).into()
+			};
+			assert_ok!(commit(public2, 1, commit_hash(vote.3[0])));
+			assert_ok!(commit(public2, 1, commit_hash(vote.3[1])));
+			assert_eq!(Voting::commitment_of((1, public2)), Some(commit_hash(vote.3[1])));
+			assert_eq!(Voting::commitment_count(1), 1);
+
+			// only the replacement commitment can be revealed
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(reveal(public2, 1, vec![vote.3[0]], Some(SECRET)), "Commitments do not match");
+			assert_ok!(reveal(public2, 1, vec![vote.3[1]], Some(SECRET)));
+		});
+	}
+
+	#[test]
+	fn scheduled_vote_should_advance_by_block() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let schedule = VoteSchedule::Blocks(None, 3);
+			assert_eq!(Ok(1), create_scheduled_vote(public, vote.0, vote.1, vote.2, &vote.3, schedule));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Voting);
+			assert_eq!(Voting::scheduled_votes(), vec![1]);
+
+			assert_ok!(reveal(public, 1, vec![vote.3[0]], None));
+			// the initiator cannot end a scheduled vote early
+			assert_err!(advance_stage_as_initiator(public, 1), "Scheduled votes advance automatically");
+			<Voting as OnFinalise<u64>>::on_finalise(2);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Voting);
+			<Voting as OnFinalise<u64>>::on_finalise(3);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Voting::scheduled_votes(), vec![]);
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 1), (vote.3[1], 0)]
+			);
+		});
+	}
+
+	#[test]
+	fn scheduled_commit_reveal_vote_should_advance_by_moment() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			Timestamp::set_timestamp(100);
+			let public = get_test_key();
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			let schedule = VoteSchedule::Moments(Some(200), 300);
+			assert_eq!(Ok(1), create_scheduled_vote(public, vote.0, vote.1, vote.2, &vote.3, schedule));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Commit);
+
+			Timestamp::set_timestamp(200);
+			<Voting as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Voting);
+			assert_eq!(Voting::scheduled_votes(), vec![1]);
+
+			Timestamp::set_timestamp(300);
+			<Voting as OnFinalise<u64>>::on_finalise(2);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Voting::scheduled_votes(), vec![]);
+		});
+	}
+
+	#[test]
+	fn scheduled_vote_past_both_stage_ends_should_complete() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_eq!(Ok(1), create_scheduled_vote(public, vote.0, vote.1, vote.2, &vote.3, VoteSchedule::Blocks(Some(2), 2)));
+			<Voting as OnFinalise<u64>>::on_finalise(5);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Voting::scheduled_votes(), vec![]);
+		});
+	}
+
+	#[test]
+	fn invalid_schedule_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(5);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let cr_vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_err!(
+				create_scheduled_vote(public, vote.0, vote.1, vote.2, &vote.3, VoteSchedule::Blocks(Some(6), 8)),
+				"Schedule must end a commit stage iff the vote is commit-reveal"
+			);
+			assert_err!(
+				create_scheduled_vote(public, cr_vote.0, cr_vote.1, cr_vote.2, &cr_vote.3, VoteSchedule::Blocks(None, 8)),
+				"Schedule must end a commit stage iff the vote is commit-reveal"
+			);
+			assert_err!(
+				create_scheduled_vote(public, vote.0, vote.1, vote.2, &vote.3, VoteSchedule::Blocks(None, 5)),
+				"Scheduled stages must end in the future"
+			);
+			assert_err!(
+				create_scheduled_vote(public, cr_vote.0, cr_vote.1, cr_vote.2, &cr_vote.3, VoteSchedule::Blocks(Some(9), 8)),
+				"Commit stage must end before voting stage"
+			);
+			assert_eq!(Voting::vote_record_count(), 0);
+		});
+	}
+
+	#[test]
+	fn simple_majority_result_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[3], 1, vec![vote.3[1]], None));
+			assert_eq!(Voting::result(1), None);
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::result(1), Some(VoteResult::Passed(vote.3[0])));
+			assert_eq!(Voting::result(2), None);
+		});
+	}
+
+	#[test]
+	fn tied_result_should_fail() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..3).map(|v| H256::from_low_u64_be(v)).collect();
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let rules = VoteRules { quorum: None, threshold: Threshold::Absolute(1), ..Default::default() };
+			assert_eq!(Ok(1), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[1]], None));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::result(1), Some(VoteResult::Failed));
+		});
+	}
+
+	#[test]
+	fn account_quorum_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			assert_ok!(delegate_to(users[3], users[2]));
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let rules = VoteRules { quorum: Some(Quorum::PercentOfAccounts(30, 10)), threshold: Threshold::SimpleMajority, ..Default::default() };
+			assert_eq!(Ok(1), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_eq!(Ok(2), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(advance_stage_as_initiator(creator, 2));
+
+			// two voters fall short of 3 in 10, but a delegator makes up the difference
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[1], 2, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[0], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 2, vec![vote.3[1]], None));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(advance_stage_as_initiator(creator, 2));
+			assert_eq!(Voting::result(1), Some(VoteResult::NoQuorum));
+			assert_eq!(Voting::result(2), Some(VoteResult::Passed(vote.3[1])));
+		});
+	}
+
+	#[test]
+	fn issuance_quorum_and_supermajority_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 30);
+			Balances::set_free_balance(&users[2], 10);
+			Balances::set_free_balance(&users[3], 60);
+			Balances::increase_total_stake_by(100);
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_err!(
+				create_vote_with_rules(creator, vote.0, TallyType::OnePerson, &vote.3,
+					VoteRules { quorum: Some(Quorum::PercentOfIssuance(40)), threshold: Threshold::SimpleMajority, ..Default::default() }),
+				"Issuance quorum requires a coin-weighted tally"
+			);
+			assert_err!(
+				create_vote_with_rules(creator, vote.0, TallyType::OneCoin, &vote.3,
+					VoteRules { quorum: None, threshold: Threshold::Supermajority(40), ..Default::default() }),
+				"Supermajority must be between 51 and 100 percent"
+			);
+
+			let rules = |percent| VoteRules { quorum: Some(Quorum::PercentOfIssuance(40)), threshold: Threshold::Supermajority(percent), ..Default::default() };
+			assert_eq!(Ok(1), create_vote_with_rules(creator, vote.0, TallyType::OneCoin, &vote.3, rules(75)));
+			assert_eq!(Ok(2), create_vote_with_rules(creator, vote.0, TallyType::OneCoin, &vote.3, rules(80)));
+			assert_eq!(Ok(3), create_vote_with_rules(creator, vote.0, TallyType::OneCoin, &vote.3, rules(75)));
+			for id in 1..4 {
+				assert_ok!(advance_stage_as_initiator(creator, id));
+			}
+
+			// 30 to 10 is exactly 75% support on 40% turnout
+			for id in 1..3 {
+				assert_ok!(reveal(users[1], id, vec![vote.3[0]], None));
+				assert_ok!(reveal(users[2], id, vec![vote.3[1]], None));
+			}
+			// 30 of 100 is below quorum
+			assert_ok!(reveal(users[1], 3, vec![vote.3[0]], None));
+			for id in 1..4 {
+				assert_ok!(advance_stage_as_initiator(creator, id));
+			}
+			assert_eq!(Voting::result(1), Some(VoteResult::Passed(vote.3[0])));
+			assert_eq!(Voting::result(2), Some(VoteResult::Failed));
+			assert_eq!(Voting::result(3), Some(VoteResult::NoQuorum));
+		});
+	}
+
+	#[test]
+	fn quadratic_tally_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Balances: 1 -> 100, 2 -> 9, 3 -> 16, delegation 3 --> 2
+			 *  Voters: 1 (0x1), 2 (0x0)
+			 *  Expected Tally: sqrt(100) = 10 for 0x1, sqrt(9) + sqrt(16) = 7 for 0x0
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 100);
+			Balances::set_free_balance(&users[2], 9);
+			Balances::set_free_balance(&users[3], 16);
+			assert_ok!(delegate_to(users[3], users[2]));
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::Quadratic, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[1]], None));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 10), (vote.3[1], 7)]
+			);
+		});
+	}
+
+	#[test]
+	fn conviction_tally_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Balances: 1 -> 10, 2 -> 20, 3 -> 5, delegation 3 --> 1
+			 *  Voters: 1 (0x1) locking 2 periods, 2 (0x0) locking none
+			 *  Expected Tally: 10 * 3 + 5 for 0x1, 20 for 0x0
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+			Balances::set_free_balance(&users[2], 20);
+			Balances::set_free_balance(&users[3], 5);
+			assert_ok!(delegate_to(users[3], users[1]));
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let binary_vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::Conviction, &vote.3));
+			assert_eq!(Ok(2), create_vote(creator, binary_vote.0, binary_vote.1, binary_vote.2, &binary_vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(advance_stage_as_initiator(creator, 2));
+
+			assert_err!(reveal_with_conviction(users[1], 1, vec![vote.3[0]], 7), "Conviction exceeds the maximum lock periods");
+			assert_err!(reveal_with_conviction(users[1], 2, vec![vote.3[0]], 1), "Conviction is only available for conviction votes");
+			assert_ok!(reveal_with_conviction(users[1], 1, vec![vote.3[0]], 2));
+			assert_ok!(reveal_with_conviction(users[2], 1, vec![vote.3[1]], 0));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 35), (vote.3[1], 20)]
+			);
+		});
+	}
+
+	#[test]
+	fn conviction_lock_should_release_after_lock_periods() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..3).map(|v| H256::from_low_u64_be(v)).collect();
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::Conviction, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal_with_conviction(users[1], 1, vec![vote.3[0]], 2));
+			assert_ok!(reveal_with_conviction(users[2], 1, vec![vote.3[0]], 0));
+
+			// locked while the vote runs
+			assert_err!(Voting::ensure_account_liquid(&users[1]), "Account is locked by a conviction vote in progress");
+			assert_ok!(Voting::ensure_account_liquid(&users[2]));
+
+			// until its lock period is started at the end of the block
+			System::set_block_number(5);
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_err!(Voting::ensure_account_liquid(&users[1]), "Account is locked by a conviction vote in progress");
+			<Voting as OnFinalise<u64>>::on_finalise(5);
+			assert!(Voting::pending_locks(&users[1]).is_empty());
+
+			// and for two periods of 10 blocks from then
+			assert_eq!(Voting::bondage(&users[1]), 25);
+			System::set_block_number(24);
+			assert_err!(Voting::ensure_account_liquid(&users[1]), "Account is locked by a conviction vote");
+			System::set_block_number(25);
+			assert_ok!(Voting::ensure_account_liquid(&users[1]));
+		});
+	}
+
+	#[test]
+	fn transfer_and_revote_should_not_double_count() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Balances: 1 -> 10, 2 -> 0
+			 *  1 votes 0x1, transfers everything to 2, and 2 votes 0x1
+			 *  Expected Tally: 10 for 0x1, not 20
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..3).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::OneCoin, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_err!(Voting::refresh_weight(Origin::signed(users[0]), 1, users[1]), "Counted weight is already up to date");
+			assert_ok!(Balances::transfer(Origin::signed(users[1]), users[2], 10));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[0]], None));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 10), (vote.3[1], 0)]
+			);
+			assert_eq!(Voting::turnout(1), (2, 10));
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::WeightRefreshed(1, users[1], 0)),
+			}));
+
+			// weights are frozen once the vote completes
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(Balances::transfer(Origin::signed(users[2]), users[1], 10));
+			assert_err!(Voting::refresh_weight(Origin::signed(users[0]), 1, users[2]), "Vote is not in voting stage");
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 10), (vote.3[1], 0)]
+			);
+			assert_eq!(Voting::contribution_of((1, users[1])), Some((vec![vote.3[0]], 0)));
+			assert_eq!(Voting::contribution_of((1, users[2])), Some((vec![vote.3[0]], 10)));
+		});
+	}
+
+	#[test]
+	fn refresh_weight_should_count_coins_received() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Balances: 1 -> 10, 2 -> 10
+			 *  1 and 2 vote 0x1, and 2 sends 4 to 1
+			 *  Expected Tally: 20 for 0x1 once 1 is refreshed
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..3).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+			Balances::set_free_balance(&users[2], 10);
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::OneCoin, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[0]], None));
+			assert_ok!(Balances::transfer(Origin::signed(users[2]), users[1], 4));
+
+			// the sender is settled before the receiver is counted up
+			assert_ok!(Voting::refresh_weight(Origin::signed(users[0]), 1, users[1]));
+			assert_eq!(Voting::contribution_of((1, users[1])), Some((vec![vote.3[0]], 14)));
+			assert_eq!(Voting::contribution_of((1, users[2])), Some((vec![vote.3[0]], 6)));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(vote.3[0], 20), (vote.3[1], 0)]
+			);
+			assert_eq!(Voting::turnout(1), (2, 20));
+		});
+	}
+
+	#[test]
+	fn approval_tally_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Delegation graph: 1 --> 2
+			 *  Ballots: 2: {A, B}, 3: {B}, 4: {C, B}
+			 *  Expected Tally: A 2, B 4, C 1, D 0
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..5).map(|v| H256::from_low_u64_be(v)).collect();
+			assert_ok!(delegate_to(H256::from_low_u64_be(500), H256::from_low_u64_be(501)));
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, b, c, d) = (vote.3[0], vote.3[1], vote.3[2], vote.3[3]);
+			assert_eq!(Ok(1), create_vote(creator, VoteType::Approval, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(reveal(users[2], 1, vec![a, b], None));
+			assert_ok!(reveal(users[3], 1, vec![b], None));
+			assert_ok!(reveal(users[4], 1, vec![c, b], None));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(a, 2), (b, 4), (c, 1), (d, 0)]
+			);
+
+			// every participant approved B
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::result(1), Some(VoteResult::Passed(b)));
+		});
+	}
+
+	#[test]
+	fn approval_commit_reveal_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, c) = (vote.3[0], vote.3[2]);
+			assert_eq!(Ok(1), create_vote(public, VoteType::Approval, true, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			// the commitment covers every approved outcome in ballot order
+			let public2 = get_test_key_2();
+			let commit_hash: [u8; 32] = compute_commitment::<_, BlakeTwo256>(&public2, &SECRET, &[a, c]).into();
+			assert_ok!(commit(public2, 1, commit_hash));
+
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(reveal(public2, 1, vec![a], Some(SECRET)), "Commitments do not match");
+			assert_ok!(reveal(public2, 1, vec![a, c], Some(SECRET)));
+			assert_eq!(
+				Voting::tally(1).unwrap(),
+				vec![(a, 1), (vote.3[1], 0), (c, 1), (vote.3[3], 0)]
+			);
+		});
+	}
+
+	#[test]
+	fn invalid_approval_vote_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, b) = (vote.3[0], vote.3[1]);
+			assert_err!(create_vote(public, VoteType::Approval, false, vote.2, &[a]), "Invalid approval outcomes");
+			assert_err!(create_vote(public, VoteType::Approval, false, vote.2, &[a, b, a]), "Vote outcomes must be unique");
+
+			assert_eq!(Ok(1), create_vote(public, VoteType::Approval, false, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(reveal(public, 1, vec![a, b, a], None), "Approval ballot contains duplicate outcomes");
+			assert_err!(reveal(public, 1, vec![a, [9; 32]], None), "Invalid vote outcome");
+			assert_err!(reveal(public, 1, vec![], None), "Ballot must not be empty");
+		});
+	}
+
+	#[test]
+	fn delegation_change_should_update_running_tally() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Balances: 1 -> 10, 2 -> 20, 3 -> 5
+			 *  Voters: 1 (0x1), 2 (0x0); 3 delegates to 1, then moves to 2
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+			Balances::set_free_balance(&users[2], 20);
+			Balances::set_free_balance(&users[3], 5);
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, TallyType::OneCoin, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::active_votes(), vec![1]);
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[1]], None));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 10), (vote.3[1], 20)]);
+
+			assert_ok!(delegate_to(users[3], users[1]));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 15), (vote.3[1], 20)]);
+			// delegators keep their balance liquid
+			assert_ok!(Voting::ensure_account_liquid(&users[3]));
+
+			assert_ok!(undelegate_from(users[3], users[1]));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 10), (vote.3[1], 20)]);
+
+			assert_ok!(delegate_to(users[3], users[2]));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 10), (vote.3[1], 25)]);
+			assert_eq!(Voting::turnout(1), (3, 35));
+			assert_eq!(
+				Voting::recount_from_graph(1).unwrap(),
+				vec![(vec![vote.3[0]], 10), (vec![vote.3[1]], 25)]
+			);
+
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert!(Voting::active_votes().is_empty());
+		});
+	}
+
+	#[test]
+	fn large_delegator_subtrees_should_be_counted_over_several_blocks() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Delegation graph: 1..=100 --> 0
+			 *  0 votes, and its delegators are counted within the reveal's
+			 *  recount budget and the rest at the end of the block
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..101).map(|v| H256::from_low_u64_be(v + 1)).collect();
+			for user in users[1..].iter() {
+				assert_ok!(delegate_to(*user, users[0]));
+			}
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(reveal(users[0], 1, vec![vote.3[0]], None));
+			assert!(Voting::turnout(1).0 < 101);
+			assert!(Voting::pending_recounts(1) > 0);
+
+			// ending the vote closes it to reveals and delegation changes, and
+			// it completes once the delegators already queued are counted
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Tallying);
+			assert!(Voting::active_votes().is_empty());
+			assert_err!(reveal(users[1], 1, vec![vote.3[1]], None), "Vote is not in voting stage");
+			// the delegation change works through the queue, but queues nothing
+			// more for the vote
+			let pending = Voting::pending_recounts(1);
+			assert_ok!(delegate_to(H256::from_low_u64_be(500), H256::from_low_u64_be(501)));
+			assert!(Voting::pending_recounts(1) <= pending);
+			assert_err!(advance_stage_as_initiator(creator, 1), "Delegators are still being counted");
+
+			<Voting as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::pending_recounts(1), 0);
+			assert_eq!(Voting::recount_head(), Voting::recount_tail());
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Voting::turnout(1).0, 101);
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 101), (vote.3[1], 0)]);
+		});
+	}
+
+	#[test]
+	#[ignore]
+	fn bench_tally_on_10k_account_delegation_graph() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  100 voters, each with 99 delegators in chains of up to 5 accounts,
+			 *  on one of 100 open votes. Run with `cargo test -- --ignored --nocapture`
+			 *  to time the slowest reveal, delegation change and block of queued
+			 *  recounts, and to compare reading the running tally against
+			 *  recomputing it through the graph.
+			 */
+			use std::time::{Duration, Instant};
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..10_000).map(|v| H256::from_low_u64_be(v + 1)).collect();
+			for (i, user) in users.iter().enumerate() {
+				Balances::set_free_balance(user, (i as u64) + 1);
+				if i % 100 != 0 {
+					// delegate 20 accounts ahead in the same group, or to its voter
+					let to = if i % 100 < 20 { i - i % 100 } else { i - 20 };
+					assert_ok!(delegate_to(*user, users[to]));
+				}
+			}
+
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			for id in 1..=100 {
+				assert_eq!(Ok(id), create_vote(creator, vote.0, vote.1, TallyType::OneCoin, &vote.3));
+				assert_ok!(advance_stage_as_initiator(creator, id));
+			}
+
+			let mut block = 1;
+			let mut slowest_block = Duration::default();
+			let finalise = |block: &mut u64, slowest: &mut Duration| {
+				let start = Instant::now();
+				<Voting as OnFinalise<u64>>::on_finalise(*block);
+				*slowest = rstd::cmp::max(*slowest, start.elapsed());
+				*block += 1;
+				System::set_block_number(*block);
+			};
+			let mut slowest_reveal = Duration::default();
+			for i in (0..10_000).step_by(100) {
+				let start = Instant::now();
+				assert_ok!(reveal(users[i], 1, vec![vote.3[(i / 100) % 2]], None));
+				slowest_reveal = rstd::cmp::max(slowest_reveal, start.elapsed());
+				finalise(&mut block, &mut slowest_block);
+			}
+			let mut slowest_delegation = Duration::default();
+			for i in (1..10_000).step_by(100) {
+				let start = Instant::now();
+				assert_ok!(undelegate_from(users[i], users[i - 1]));
+				slowest_delegation = rstd::cmp::max(slowest_delegation, start.elapsed());
+				assert_ok!(delegate_to(users[i], users[i - 1]));
+				finalise(&mut block, &mut slowest_block);
+			}
+			while Voting::recount_head() < Voting::recount_tail() {
+				finalise(&mut block, &mut slowest_block);
+			}
+			println!(
+				"slowest reveal {:?}, delegation change {:?}, block of recounts {:?}, {} blocks in all",
+				slowest_reveal, slowest_delegation, slowest_block, block - 1
+			);
+
+			let start = Instant::now();
+			let tally = Voting::tally(1).unwrap();
+			let running = start.elapsed();
+			let start = Instant::now();
+			let recounted = Voting::recount_from_graph(1).unwrap();
+			let recomputed = start.elapsed();
+			println!("running tally read in {:?}, recomputed in {:?}", running, recomputed);
+
+			assert_eq!(Voting::turnout(1).0, 10_000);
+			for (outcome, weight) in tally {
+				let expected = recounted.iter().find(|(b, _)| b == &vec![outcome]).map_or(0, |(_, w)| *w);
+				assert_eq!(weight, expected);
+			}
+		});
+	}
+
+	#[test]
+	fn legacy_vote_records_should_migrate() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			assert_ok!(delegate_to(users[3], users[2]));
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+
+			// a record laid out as before storage version 1, with its
+			// commitments and single outcome reveals inline
+			let commitments: Vec<(H256, [u8; 32])> = vec![(users[1], [7; 32])];
+			let reveals: Vec<(H256, [u8; 32])> = vec![(users[1], vote.3[0]), (users[2], vote.3[1])];
+			let data = (creator, VoteStage::Voting, vote.0, vote.2, vote.1);
+			let legacy = (1u64, commitments, reveals, data, vote.3.to_vec());
+			runtime_support::storage::put(&<voting::VoteRecords<Test>>::key_for(1), &legacy);
+			<voting::VoteRecordCount<Test>>::put(1);
+			assert_eq!(Voting::vote_records(1), None);
+
+			<Voting as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::storage_version(), 1);
+			assert_eq!(
+				Voting::vote_records(1),
+				Some(make_record(1, creator, vote.0, vote.1, vote.2, &vote.3, VoteStage::Voting))
+			);
+			assert_eq!(Voting::commitment_of((1, users[1])), Some([7; 32]));
+			assert_eq!(Voting::voters_of(1), vec![users[1], users[2]]);
+			assert_eq!(Voting::reveal_of((1, users[2])), Some(vec![vote.3[1]]));
+			assert_eq!(Voting::active_votes(), vec![1]);
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 1), (vote.3[1], 2)]);
+		});
+	}
+
+	#[test]
+	fn undecodable_vote_records_should_not_raise_the_storage_version() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let data = (creator, VoteStage::PreVoting, vote.0, vote.2, vote.1);
+			let legacy = (1u64, Vec::<(H256, [u8; 32])>::new(), Vec::<(H256, [u8; 32])>::new(), data, vote.3.to_vec());
+			runtime_support::storage::put(&<voting::VoteRecords<Test>>::key_for(1), &legacy);
+			runtime_support::storage::put(&<voting::VoteRecords<Test>>::key_for(2), &vec![0xffu8; 3]);
+			<voting::VoteRecordCount<Test>>::put(2);
+
+			<Voting as OnFinalise<u64>>::on_finalise(1);
+			assert_eq!(Voting::storage_version(), 0);
+			assert_eq!(Voting::unmigrated_votes(), vec![2]);
+			assert_eq!(
+				Voting::vote_records(1),
+				Some(make_record(1, creator, vote.0, vote.1, vote.2, &vote.3, VoteStage::PreVoting))
+			);
+
+			// later blocks leave the listed records alone
+			<Voting as OnFinalise<u64>>::on_finalise(2);
+			assert_eq!(Voting::unmigrated_votes(), vec![2]);
+			assert_eq!(Voting::storage_version(), 0);
+		});
+	}
+
+	#[test]
+	fn cancel_vote_before_voting_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let public2 = get_test_key_2();
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(cancel_vote(public2, 1), "Invalid cancel attempt by non-owner");
+			assert_ok!(cancel_vote(public, 1));
+			assert_eq!(
+				Voting::vote_records(1),
+				Some(make_record(1, public, vote.0, vote.1, vote.2, &vote.3, VoteStage::Cancelled))
+			);
+			assert_eq!(System::events()[2], EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::VoteCancelled(1, VoteStage::Commit))
+			});
+
+			assert_eq!(Voting::tally(1), None);
+			assert_eq!(Voting::result(1), None);
+			assert_err!(commit(public2, 1, [1; 32]), "Vote is not in commit stage");
+			assert_err!(advance_stage_as_initiator(public, 1), "Vote was cancelled");
+			assert_err!(cancel_vote(public, 1), "Vote has already begun");
+			assert_err!(force_cancel_vote(1), "Vote is already finished");
+		});
+	}
+
+	#[test]
+	fn cancel_vote_during_voting_should_need_privileged_origin() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..2).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let schedule = VoteSchedule::Blocks(None, 10);
+			assert_eq!(Ok(1), create_scheduled_vote(public, vote.0, vote.1, TallyType::OneCoin, &vote.3, schedule));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+
+			assert_err!(cancel_vote(public, 1), "Vote has already begun");
+			assert_err!(Voting::force_cancel_vote(Origin::signed(public), 1), "bad origin: expected to be a root origin");
+			assert_ok!(force_cancel_vote(1));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Cancelled);
+			assert_eq!(CANCELLED.with(|c| c.borrow().clone()), vec![1]);
+			assert_eq!(Voting::tally(1), None);
+			assert!(Voting::active_votes().is_empty());
+			assert!(Voting::scheduled_votes().is_empty());
+			assert_err!(reveal(users[1], 1, vec![vote.3[1]], None), "Vote is not in voting stage");
+		});
+	}
+
+	#[test]
+	fn allowlist_vote_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			/*  Allowlist: 1, 2 (2 added once voting is open)
+			 *  Delegation graph: 2 --> 3, 4 --> 1
+			 *  Voters: 1 (0x1), 3 (0x0), 4 turned away
+			 *  Expected Tally: 1 for 0x1, 2 added to 0x0 only once 3 is allowed
+			 */
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..5).map(|v| H256::from_low_u64_be(v)).collect();
+			assert_ok!(delegate_to(users[2], users[3]));
+			assert_ok!(delegate_to(users[4], users[1]));
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			let rules = VoteRules { eligibility: VoterPolicy::Allowlist, ..Default::default() };
+			assert_eq!(Ok(1), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_err!(allow_voters(users[1], 1, vec![users[1]]), "Invalid allowlist attempt by non-owner");
+			assert_ok!(allow_voters(creator, 1, vec![users[1], users[2]]));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_err!(reveal(users[3], 1, vec![vote.3[1]], None), "Sender is not eligible to vote");
+			assert_err!(reveal(users[4], 1, vec![vote.3[1]], None), "Sender is not eligible to vote");
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 1), (vote.3[1], 0)]);
+
+			assert_ok!(allow_voters(creator, 1, vec![users[3]]));
+			assert_ok!(reveal(users[3], 1, vec![vote.3[1]], None));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(vote.3[0], 1), (vote.3[1], 2)]);
+		});
+	}
+
+	#[test]
+	fn min_balance_and_external_eligibility_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let users : Vec<H256> = (0..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[1], 10);
+			Balances::set_free_balance(&users[2], 5);
+			let creator = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+
+			let rules = VoteRules { eligibility: VoterPolicy::MinBalance(10), ..Default::default() };
+			assert_eq!(Ok(1), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[1], 1, vec![vote.3[0]], None));
+			assert_err!(reveal(users[2], 1, vec![vote.3[0]], None), "Sender is not eligible to vote");
+
+			// the provider approves even accounts only
+			let rules = VoteRules { eligibility: VoterPolicy::External, ..Default::default() };
+			assert_eq!(Ok(2), create_vote_with_rules(creator, vote.0, vote.2, &vote.3, rules));
+			assert_ok!(advance_stage_as_initiator(creator, 2));
+			assert_ok!(reveal(users[2], 2, vec![vote.3[0]], None));
+			assert_err!(reveal(users[3], 2, vec![vote.3[0]], None), "Sender is not eligible to vote");
+			assert_err!(allow_voters(creator, 2, vec![users[3]]), "Vote does not use an allowlist");
+		});
+	}
+
+	#[test]
+	fn commit_bond_should_be_returned_on_reveal_and_slashed_otherwise() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<voting::CommitBond<Test>>::put(10);
+			let creator = get_test_key();
+			let users : Vec<H256> = (1..4).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[0], 25);
+			Balances::set_free_balance(&users[1], 25);
+			Balances::set_free_balance(&users[2], 5);
+			Balances::increase_total_stake_by(55);
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			let commit_hash = |who: H256, outcome: [u8; 32]| -> [u8; 32] {
+				compute_commitment::<_, BlakeTwo256>(&who, &SECRET, &[outcome]).into()
+			};
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+
+			assert_ok!(commit(users[0], 1, commit_hash(users[0], vote.3[0])));
+			assert_ok!(commit(users[1], 1, commit_hash(users[1], vote.3[1])));
+			assert!(commit(users[2], 1, commit_hash(users[2], vote.3[1])).is_err());
+			// replacing a commitment does not bond again
+			assert_ok!(commit(users[0], 1, commit_hash(users[0], vote.3[1])));
+			assert_eq!(Balances::reserved_balance(&users[0]), 10);
+			assert_eq!(Balances::free_balance(&users[0]), 15);
+
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(users[0], 1, vec![vote.3[1]], Some(SECRET)));
+			assert_eq!(Balances::reserved_balance(&users[0]), 0);
+			assert_eq!(Balances::free_balance(&users[0]), 25);
+			assert_eq!(Voting::commit_bond_of((1, users[0])), None);
+
+			// users[1] never reveals
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Balances::reserved_balance(&users[1]), 0);
+			assert_eq!(Balances::free_balance(&users[1]), 15);
+			assert_eq!(Balances::total_issuance(), 45);
+			assert_eq!(Voting::commit_bond_of((1, users[1])), None);
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::CommitBondSlashed(1, users[1], 10))
+			}));
+		});
+	}
+
+	#[test]
+	fn commit_bond_should_be_returned_on_cancel() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<voting::CommitBond<Test>>::put(10);
+			let creator = get_test_key();
+			let voter = H256::from_low_u64_be(1);
+			Balances::set_free_balance(&voter, 25);
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_eq!(Ok(1), create_vote(creator, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(commit(voter, 1, SECRET));
+			assert_eq!(Balances::reserved_balance(&voter), 10);
+
+			assert_ok!(cancel_vote(creator, 1));
+			assert_eq!(Balances::reserved_balance(&voter), 0);
+			assert_eq!(Balances::free_balance(&voter), 25);
+			assert_eq!(Voting::commit_bond_of((1, voter)), None);
+		});
+	}
+
+	#[test]
+	fn create_vote_as_signed_should_reserve_deposit_until_completed() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<voting::CreationDeposit<Test>>::put(100);
+			let creator = get_test_key();
+			let poor = get_test_key_2();
+			Balances::set_free_balance(&creator, 150);
+			let vote = generate_1p1v_public_binary_vote();
+
+			assert!(create_vote_as_signed(poor, vote.0, vote.3.to_vec()).is_err());
+			assert_err!(create_vote_as_signed(creator, vote.0, vec![vote.3[0], vote.3[0]]), "Vote outcomes must be unique");
+			let many: Vec<[u8; 32]> = (0..17).map(|i| [i; 32]).collect();
+			assert_err!(create_vote_as_signed(creator, VoteType::MultiOption, many), "Too many vote outcomes");
+			// failed attempts keep nothing reserved
+			assert_eq!(Balances::reserved_balance(&creator), 0);
+
+			assert_ok!(create_vote_as_signed(creator, vote.0, vote.3.to_vec()));
+			assert_eq!(Voting::vote_records(1).unwrap().data.initiator, creator);
+			assert_eq!(Balances::reserved_balance(&creator), 100);
+			assert_eq!(Voting::creation_deposit_of(1), Some((creator, 100)));
+
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_ok!(reveal(poor, 1, vec![vote.3[0]], None));
+			assert_ok!(advance_stage_as_initiator(creator, 1));
+			assert_eq!(Balances::reserved_balance(&creator), 0);
+			assert_eq!(Balances::free_balance(&creator), 150);
+			assert_eq!(Voting::creation_deposit_of(1), None);
+		});
+	}
+
+	#[test]
+	fn force_cancel_should_slash_creation_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<voting::CreationDeposit<Test>>::put(100);
+			let creator = get_test_key();
+			Balances::set_free_balance(&creator, 300);
+			let vote = generate_1p1v_public_binary_vote();
+
+			// cancelling their own vote returns the initiator's deposit
+			assert_ok!(create_vote_as_signed(creator, vote.0, vote.3.to_vec()));
+			assert_ok!(cancel_vote(creator, 1));
+			assert_eq!(Balances::free_balance(&creator), 300);
+
+			assert_ok!(create_vote_as_signed(creator, vote.0, vote.3.to_vec()));
+			assert_ok!(force_cancel_vote(2));
+			assert_eq!(Balances::reserved_balance(&creator), 0);
+			assert_eq!(Balances::free_balance(&creator), 200);
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::CreationDepositSlashed(2, creator, 100))
+			}));
+		});
+	}
+
+	#[test]
+	fn finished_votes_should_be_pruned_after_retention_period() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			Timestamp::set_timestamp(100);
+			<voting::RetentionPeriod<Test>>::put(5);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..4).map(|v| H256::from_low_u64_be(v)).collect();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(reveal(users[0], 1, vec![vote.3[0]], None));
+			assert_ok!(delegate_to(users[1], users[0]));
+			assert_ok!(reveal(users[2], 1, vec![vote.3[1]], None));
+
+			Timestamp::set_timestamp(200);
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			let tally = Voting::tally(1);
+			assert_eq!(tally, Some(vec![(vote.3[0], 2), (vote.3[1], 1)]));
+
+			<Voting as OnFinalise<u64>>::on_finalise(5);
+			assert!(Voting::vote_records(1).is_some());
+
+			<Voting as OnFinalise<u64>>::on_finalise(6);
+			assert_eq!(Voting::vote_records(1), None);
+			assert_eq!(Voting::reveal_of((1, users[0])), None);
+			assert_eq!(Voting::contribution_of((1, users[1])), None);
+			assert_eq!(Voting::voters_of(1), vec![]);
+			assert_eq!(Voting::ballot_totals(1), vec![]);
+			assert_eq!(Voting::finished_votes(), vec![]);
+			let ballots_hash = BlakeTwo256::hash_of(&(&H256::default(), &users[0], &vec![vote.3[0]]));
+			let ballots_hash = BlakeTwo256::hash_of(&(&ballots_hash, &users[2], &vec![vote.3[1]]));
+			assert_eq!(Voting::pruned_vote(1), Some(PrunedVote {
+				tally: tally.clone(),
+				ballots_hash: ballots_hash,
+				stage_times: vec![
+					(VoteStage::PreVoting, 100),
+					(VoteStage::Voting, 100),
+					(VoteStage::Completed, 200),
+				],
+			}));
+			assert_eq!(Voting::tally(1), tally);
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::VotePruned(1))
+			}));
+		});
+	}
+
+	#[test]
+	fn large_votes_should_be_pruned_over_several_blocks() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			<voting::RetentionPeriod<Test>>::put(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..101).map(|v| H256::from_low_u64_be(v)).collect();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_eq!(Ok(2), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			for who in users.iter() {
+				assert_ok!(reveal(*who, 1, vec![vote.3[0]], None));
+			}
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(cancel_vote(public, 2));
+
+			// each voter takes three removals and each contributor three more
+			<Voting as OnFinalise<u64>>::on_finalise(2);
+			assert_eq!(Voting::pruning().map(|cursor| (cursor.vote_id, cursor.step)), Some((1, PruneStep::Voters(86))));
+			assert!(Voting::vote_records(1).is_some());
+			assert_eq!(Voting::reveal_of((1, users[85])), None);
+			assert_eq!(Voting::reveal_of((1, users[86])), Some(vec![vote.3[0]]));
+			assert_eq!(Voting::finished_votes(), vec![(2, 1)]);
+
+			<Voting as OnFinalise<u64>>::on_finalise(3);
+			assert_eq!(Voting::pruning().map(|cursor| cursor.step), Some(PruneStep::Contributors(72)));
+
+			// the second vote is pruned in the same block as the rest of the first
+			<Voting as OnFinalise<u64>>::on_finalise(4);
+			assert_eq!(Voting::pruning(), None);
+			assert_eq!(Voting::vote_records(1), None);
+			assert_eq!(Voting::vote_records(2), None);
+			assert_eq!(Voting::contribution_of((1, users[99])), None);
+			assert_eq!(Voting::finished_votes(), vec![]);
+			let ballots_hash = users.iter()
+				.fold(H256::default(), |hash, who| BlakeTwo256::hash_of(&(&hash, who, &vec![vote.3[0]])));
+			assert_eq!(Voting::pruned_vote(1).unwrap().ballots_hash, ballots_hash);
+			assert_eq!(Voting::tally(1), Some(vec![(vote.3[0], 100), (vote.3[1], 0)]));
+		});
+	}
+
+	#[test]
+	fn completed_vote_should_break_down_per_voter() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..6).map(|v| H256::from_low_u64_be(v)).collect();
+			(0..5).for_each(|i| Balances::set_free_balance(&users[i], 10 * (i as u64 + 1)));
+			let (vote_type, _, _, outcomes) = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote_type, false, TallyType::OneCoin, &outcomes));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			// users[2] -> users[1] -> users[0], users[4] -> users[3]
+			assert_ok!(delegate_to(users[1], users[0]));
+			assert_ok!(delegate_to(users[2], users[1]));
+			assert_ok!(delegate_to(users[4], users[3]));
+			assert_ok!(reveal(users[0], 1, vec![outcomes[0]], None));
+			assert_ok!(reveal(users[3], 1, vec![outcomes[1]], None));
+			// moving a delegation during the vote moves the weight with it
+			assert_ok!(undelegate_from(users[4], users[3]));
+			assert_ok!(delegate_to(users[4], users[0]));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let breakdown = Voting::compute_breakdown(1);
+			assert_eq!(breakdown.len(), 5);
+			assert!(breakdown.contains(&(users[2], users[0], vec![outcomes[0]], 30)));
+			assert!(breakdown.contains(&(users[4], users[0], vec![outcomes[0]], 50)));
+			assert!(breakdown.contains(&(users[3], users[3], vec![outcomes[1]], 40)));
+
+			// the breakdown reproduces the tally
+			let yes = breakdown.iter().filter(|b| b.2 == vec![outcomes[0]]).fold(0, |acc, b| acc + b.3);
+			let no = breakdown.iter().filter(|b| b.2 == vec![outcomes[1]]).fold(0, |acc, b| acc + b.3);
+			assert_eq!(Voting::tally(1).unwrap(), vec![(outcomes[0], yes), (outcomes[1], no)]);
+			assert_eq!((yes, no), (110, 40));
+		});
+	}
+
+	#[test]
+	fn completing_a_vote_should_notify_handler() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_eq!(Ok(2), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(advance_stage_as_initiator(public, 2));
+			assert_ok!(reveal(public, 1, vec![vote.3[0]], None));
+			assert_ok!(force_cancel_vote(2));
+			assert_eq!(COMPLETED.with(|c| c.borrow().clone()), vec![]);
+
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_eq!(COMPLETED.with(|c| c.borrow().clone()), vec![(1, VoteResult::Passed(vote.3[0]))]);
+		});
+	}
+
+	#[test]
+	fn compute_commitment_should_match_documented_layout() {
+		let who = get_test_key_2();
+		let ballot = [[3; 32], [5; 32]];
+		let mut preimage = vec![];
+		preimage.extend_from_slice(&<[u8; 32]>::from(who));
+		preimage.extend_from_slice(&SECRET);
+		preimage.extend_from_slice(&ballot[0]);
+		preimage.extend_from_slice(&ballot[1]);
+		assert_eq!(commitment_preimage(&who, &SECRET, &ballot), preimage);
+		assert_eq!(
+			compute_commitment::<_, BlakeTwo256>(&who, &SECRET, &ballot),
+			BlakeTwo256::hash(&preimage.encode())
+		);
+	}
+
+	#[test]
+	fn computed_commitment_should_round_trip_through_reveal() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, true, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let voter = get_test_key_2();
+			let ballot = vec![vote.3[2], vote.3[0]];
+			let secret = [9; 32];
+			let commitment: [u8; 32] = compute_commitment::<_, BlakeTwo256>(&voter, &secret, &ballot).into();
+			assert_ok!(commit(voter, 1, commitment));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_err!(reveal(voter, 1, ballot.clone(), Some(SECRET)), "Commitments do not match");
+			assert_err!(reveal(voter, 1, vec![vote.3[0], vote.3[2]], Some(secret)), "Commitments do not match");
+			assert_ok!(reveal(voter, 1, ballot.clone(), Some(secret)));
+			assert_eq!(Voting::reveal_of((1, voter)), Some(ballot));
+		});
+	}
+
+	#[test]
+	fn reveal_without_secret_should_fail() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_commit_reveal_binary_vote();
+			assert_eq!(Ok(1), create_vote(public, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			let public2 = get_test_key_2();
+			let commit_hash: [u8; 32] = compute_commitment::<_, BlakeTwo256>(&public2, &SECRET, &[vote.3[0]]).into();
+			assert_ok!(commit(public2, 1, commit_hash));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			assert_err!(reveal(public, 1, vec![vote.3[0]], Some(SECRET)), "Sender has not committed");
+			assert_err!(reveal(public2, 1, vec![vote.3[0]], None), "Commit-reveal votes must be revealed with a secret");
+			assert_ok!(reveal(public2, 1, vec![vote.3[0]], Some(SECRET)));
+		});
+	}
+
+	#[test]
+	fn create_committee_vote_should_check_seats_and_tally() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_multi_vote();
+			assert_err!(
+				create_vote(public, VoteType::Committee(0), false, TallyType::OneCoin, &vote.3),
+				"Committee must have fewer seats than outcomes"
+			);
+			assert_err!(
+				create_vote(public, VoteType::Committee(4), false, TallyType::OneCoin, &vote.3),
+				"Committee must have fewer seats than outcomes"
+			);
+			assert_err!(
+				create_vote(public, VoteType::Committee(2), false, TallyType::OnePerson, &vote.3),
+				"Committee elections require a coin-weighted tally"
+			);
+			assert_eq!(Ok(1), create_vote(public, VoteType::Committee(2), false, TallyType::OneCoin, &vote.3));
+		});
+	}
+
+	#[test]
+	fn committee_vote_should_elect_by_sequential_phragmen() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..5).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[0], 10);
+			Balances::set_free_balance(&users[1], 20);
+			Balances::set_free_balance(&users[2], 5);
+			Balances::set_free_balance(&users[3], 15);
+			let vote = generate_1p1v_public_multi_vote();
+			let (a, b) = (vote.3[0], vote.3[1]);
+			assert_eq!(Ok(1), create_vote(public, VoteType::Committee(2), false, TallyType::OneCoin, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			assert_ok!(reveal(users[0], 1, vec![b], None));
+			assert_ok!(reveal(users[1], 1, vec![a, b], None));
+			assert_ok!(reveal(users[2], 1, vec![a], None));
+			assert_err!(reveal(users[2], 1, vec![a, a], None), "Approval ballot contains duplicate outcomes");
+			// delegated weight approves its representative's outcomes
+			assert_ok!(delegate_to(users[3], users[1]));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			// b has the most support, after which a's approvers carry less load
+			// than any other outcome's
+			assert_eq!(Voting::compute_committee(1), Some(CommitteeResult {
+				elected: vec![b, a],
+				distribution: vec![
+					(users[0], vec![(b, 10)]),
+					(users[1], vec![(b, 10), (a, 10)]),
+					(users[2], vec![(a, 5)]),
+					(users[3], vec![(b, 7), (a, 7)]),
+				],
+			}));
+			assert_eq!(Voting::tally(1).unwrap(), vec![(a, 40), (b, 45), (vote.3[2], 0), (vote.3[3], 0)]);
+			assert_eq!(Voting::result(1), Some(VoteResult::Elected(2)));
+		});
+	}
+
+	#[test]
+	fn scalar_outcomes_should_round_trip() {
+		assert_eq!(scalar_outcome(0), [0; 32]);
+		assert_eq!(scalar_outcome(258)[30..], [1, 2]);
+		assert_eq!(scalar_value(&scalar_outcome(u64::max_value())), Some(u64::max_value()));
+		assert!(scalar_outcome(255) < scalar_outcome(256));
+		assert_eq!(scalar_value(&[1; 32]), None);
+	}
+
+	#[test]
+	fn create_scalar_vote_should_check_range() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_err!(
+				create_vote(public, VoteType::Scalar(0, 100, 5), false, TallyType::OneCoin, &vote.3),
+				"Scalar votes take a range instead of outcomes"
+			);
+			assert_err!(create_vote(public, VoteType::Scalar(100, 100, 5), false, TallyType::OneCoin, &[]), "Invalid scalar range");
+			assert_err!(create_vote(public, VoteType::Scalar(0, 100, 0), false, TallyType::OneCoin, &[]), "Invalid scalar range");
+			assert_eq!(Ok(1), create_vote(public, VoteType::Scalar(0, 100, 5), false, TallyType::OneCoin, &[]));
+		});
+	}
+
+	#[test]
+	fn scalar_vote_should_tally_weighted_median() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..5).map(|v| H256::from_low_u64_be(v)).collect();
+			Balances::set_free_balance(&users[0], 10);
+			Balances::set_free_balance(&users[1], 30);
+			Balances::set_free_balance(&users[2], 15);
+			Balances::set_free_balance(&users[3], 35);
+			assert_eq!(Ok(1), create_vote(public, VoteType::Scalar(0, 100, 5), false, TallyType::OneCoin, &[]));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			let single_value = "Vote must select a single value in the scalar range";
+			assert_err!(reveal(users[0], 1, vec![scalar_outcome(53)], None), single_value);
+			assert_err!(reveal(users[0], 1, vec![scalar_outcome(105)], None), single_value);
+			assert_err!(reveal(users[0], 1, vec![scalar_outcome(20), scalar_outcome(25)], None), single_value);
+			assert_err!(reveal(users[0], 1, vec![[1; 32]], None), single_value);
+
+			assert_ok!(reveal(users[0], 1, vec![scalar_outcome(20)], None));
+			assert_ok!(reveal(users[1], 1, vec![scalar_outcome(50)], None));
+			assert_ok!(reveal(users[2], 1, vec![scalar_outcome(80)], None));
+			assert_eq!(Voting::tally(1), Some(vec![(scalar_outcome(50), 55)]));
+
+			// delegated weight follows the representative's value
+			assert_ok!(delegate_to(users[3], users[2]));
+			assert_eq!(Voting::tally(1), Some(vec![(scalar_outcome(80), 90)]));
+
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_eq!(Voting::result(1), Some(VoteResult::Passed(scalar_outcome(80))));
+		});
+	}
+
+	#[test]
+	fn signed_ballots_should_be_revealed_for_their_signers() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let relayer = get_test_key();
+			let pair: Pair = Pair::from_seed(&hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f61"));
+			let signer = get_test_key_2();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(relayer, vote.0, vote.1, vote.2, &vote.3));
+			assert_ok!(advance_stage_as_initiator(relayer, 1));
+			assert_err!(submit_signed_ballots(relayer, vec![]), "No signed ballots to submit");
+
+			let first = sign_relayed_ballot(&pair, 1, vec![vote.3[0]], 0);
+			assert_ok!(submit_signed_ballots(relayer, vec![first.clone()]));
+			assert_eq!(Voting::reveal_of((1, signer)), Some(vec![vote.3[0]]));
+			assert_eq!(Voting::reveal_of((1, relayer)), None);
+			assert_eq!(Voting::ballot_nonce(signer), 1);
+			assert_eq!(Balances::free_balance(&signer), 0);
+
+			// replayed, tampered and other chains' ballots are skipped
+			let mut tampered = sign_relayed_ballot(&pair, 1, vec![vote.3[0]], 1);
+			tampered.ballot = vec![vote.3[1]];
+			let other_payload = signed_ballot_payload(&H256::from_low_u64_be(7), 1, &[vote.3[1]], 1);
+			let other_chain = SignedBallot {
+				signature: Ed25519Signature(pair.sign(&other_payload).0.into()),
+				..sign_relayed_ballot(&pair, 1, vec![vote.3[1]], 1)
+			};
+			assert_ok!(submit_signed_ballots(relayer, vec![first, tampered, other_chain]));
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::SignedBallotRejected(1, signer, 0))
+			}));
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::SignedBallotRejected(1, signer, 1))
+			}));
+			assert_eq!(Voting::ballot_nonce(signer), 1);
+			assert_eq!(Voting::tally(1), Some(vec![(vote.3[0], 1), (vote.3[1], 0)]));
+
+			// a ballot with the next nonce replaces the earlier one
+			assert_ok!(submit_signed_ballots(relayer, vec![sign_relayed_ballot(&pair, 1, vec![vote.3[1]], 1)]));
+			assert_eq!(Voting::ballot_nonce(signer), 2);
+			assert_eq!(Voting::tally(1), Some(vec![(vote.3[0], 0), (vote.3[1], 1)]));
+		});
+	}
+
+	#[test]
+	fn failed_signed_ballot_reveals_should_use_up_their_nonce() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let relayer = get_test_key();
+			let pair: Pair = Pair::from_seed(&hex!("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f61"));
+			let signer = get_test_key_2();
+			let vote = generate_1p1v_public_binary_vote();
+			assert_eq!(Ok(1), create_vote(relayer, vote.0, vote.1, vote.2, &vote.3));
+
+			// the vote is not open for voting yet
+			assert_ok!(submit_signed_ballots(relayer, vec![sign_relayed_ballot(&pair, 1, vec![vote.3[0]], 0)]));
+			assert_eq!(Voting::reveal_of((1, signer)), None);
+			assert_eq!(Voting::ballot_nonce(signer), 1);
+
+			assert_ok!(advance_stage_as_initiator(relayer, 1));
+			assert_ok!(submit_signed_ballots(relayer, vec![sign_relayed_ballot(&pair, 1, vec![vote.3[0]], 0)]));
+			assert_eq!(Voting::reveal_of((1, signer)), None);
+			assert_ok!(submit_signed_ballots(relayer, vec![sign_relayed_ballot(&pair, 1, vec![vote.3[0]], 1)]));
+			assert_eq!(Voting::reveal_of((1, signer)), Some(vec![vote.3[0]]));
+		});
+	}
+
+	#[test]
+	fn threshold_key_should_be_checked_when_set() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let (key, _) = threshold::deal(&[7; 32], &[[9; 32]], 3);
+			assert!(Voting::set_threshold_key(Origin::signed(get_test_key()), key.clone()).is_err());
+
+			let mut swapped = key.clone();
+			swapped.shares.swap(1, 2);
+			assert_err!(Voting::set_threshold_key(system::RawOrigin::Root.into(), swapped), "Invalid threshold key");
+			let mut unreachable = key.clone();
+			unreachable.threshold = 4;
+			assert_err!(Voting::set_threshold_key(system::RawOrigin::Root.into(), unreachable), "Invalid threshold key");
+
+			assert_eq!(Voting::authority_key(), None);
+			assert_ok!(Voting::set_threshold_key(system::RawOrigin::Root.into(), key.clone()));
+			assert_eq!(Voting::authority_key(), Some(key));
+		});
+	}
+
+	#[test]
+	fn vote_parameters_should_be_set_by_privileged_origin() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			assert!(Voting::set_vote_parameters(Origin::signed(get_test_key()), 5, 8, 1, 20, 3, 100, 30).is_err());
+			assert_err!(
+				Voting::set_vote_parameters(system::RawOrigin::Root.into(), 5, 1, 1, 20, 3, 100, 30),
+				"Votes must be allowed at least two outcomes"
+			);
+			assert_eq!(Voting::max_outcomes(), 16);
+
+			assert_ok!(Voting::set_vote_parameters(system::RawOrigin::Root.into(), 5, 8, 1, 20, 3, 100, 30));
+			assert_eq!(Voting::creation_deposit(), 5);
+			assert_eq!(Voting::max_outcomes(), 8);
+			assert_eq!(Voting::commit_bond(), 1);
+			assert_eq!(Voting::lock_period(), 20);
+			assert_eq!(Voting::max_lock_periods(), 3);
+			assert_eq!(Voting::retention_period(), 100);
+			assert_eq!(Voting::decryption_period(), 30);
+		});
+	}
+
+	#[test]
+	fn encrypted_vote_should_be_decrypted_by_authorities() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..5).map(|v| H256::from_low_u64_be(v)).collect();
+			let vote = generate_1p1v_public_binary_vote();
+			let (yes, no) = (vote.3[0], vote.3[1]);
+			// any two of the three authorities can decrypt
+			let (key, secrets) = threshold::deal(&[7; 32], &[[9; 32]], 3);
+			assert_err!(create_encrypted_vote(public, &vote.3), "No threshold key has been set");
+			assert_ok!(Voting::set_threshold_key(system::RawOrigin::Root.into(), key.clone()));
+			assert_eq!(Ok(1), create_encrypted_vote(public, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			assert_err!(reveal(users[0], 1, vec![yes], None), "Encrypted votes must be cast with an encrypted ballot");
+			// a binary vote has no room for a ballot of three outcomes
+			assert_err!(cast_encrypted(users[0], 1, &key, 1, vec![yes, no, yes]), "Ciphertext is too long");
+			assert_ok!(cast_encrypted(users[0], 1, &key, 1, vec![yes]));
+			assert_ok!(cast_encrypted(users[1], 1, &key, 2, vec![no]));
+			// encrypted for another voter, so it decrypts to garbage
+			let copied = Voting::ciphertext_of((1, users[0])).unwrap();
+			assert_ok!(Voting::cast_encrypted(Origin::signed(users[2]), 1, copied));
+			assert_ok!(delegate_to(users[3], users[0]));
+			assert_eq!(Voting::tally(1), Some(vec![(yes, 0), (no, 0)]));
+
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Decrypting);
+			assert_err!(cast_encrypted(users[0], 1, &key, 3, vec![no]), "Vote is not in voting stage");
+			assert_err!(advance_stage_as_initiator(public, 1), "Encrypted ballots are still being decrypted");
+
+			assert_ok!(Voting::submit_decryption_shares(Origin::signed(public), 1, decryption_shares(1, 1, &secrets[0], &users[..3])));
+			assert_eq!(Voting::pending_decryptions(1), 3);
+			let mut forged = decryption_shares(1, 2, &secrets[1], &users[..1]);
+			forged[0].1.index = 3;
+			assert_err!(Voting::submit_decryption_shares(Origin::signed(public), 1, forged), "Invalid decryption share");
+
+			assert_ok!(Voting::submit_decryption_shares(Origin::signed(public), 1, decryption_shares(1, 3, &secrets[2], &users[..3])));
+			assert_eq!(Voting::pending_decryptions(1), 0);
+			assert_eq!(Voting::reveal_of((1, users[0])), Some(vec![yes]));
+			assert_eq!(Voting::reveal_of((1, users[2])), None);
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::EncryptedBallotDiscarded(1, users[2]))
+			}));
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert_eq!(Voting::tally(1), Some(vec![(yes, 2), (no, 1)]));
+			assert_eq!(Voting::result(1), Some(VoteResult::Passed(yes)));
+		});
+	}
+
+	#[test]
+	fn encrypted_vote_should_complete_after_decryption_deadline() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let users : Vec<H256> = (1..3).map(|v| H256::from_low_u64_be(v)).collect();
+			let vote = generate_1p1v_public_binary_vote();
+			let (yes, no) = (vote.3[0], vote.3[1]);
+			let (key, secrets) = threshold::deal(&[7; 32], &[[9; 32]], 3);
+			assert_ok!(Voting::set_threshold_key(system::RawOrigin::Root.into(), key.clone()));
+			assert_eq!(Ok(1), create_encrypted_vote(public, &vote.3));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(cast_encrypted(users[0], 1, &key, 1, vec![yes]));
+			assert_ok!(cast_encrypted(users[1], 1, &key, 2, vec![no]));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_eq!(Voting::decryption_deadline(1), Some(11));
+			assert_eq!(Voting::decrypting_votes(), vec![1]);
+
+			// only the first ballot gets enough shares
+			assert_ok!(Voting::submit_decryption_shares(Origin::signed(public), 1, decryption_shares(1, 1, &secrets[0], &users[..1])));
+			assert_ok!(Voting::submit_decryption_shares(Origin::signed(public), 1, decryption_shares(1, 2, &secrets[1], &users[..1])));
+			assert_eq!(Voting::pending_decryptions(1), 1);
+
+			System::set_block_number(10);
+			<Voting as OnFinalise<u64>>::on_finalise(10);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Decrypting);
+
+			System::set_block_number(11);
+			<Voting as OnFinalise<u64>>::on_finalise(11);
+			assert_eq!(Voting::vote_records(1).unwrap().data.stage, VoteStage::Completed);
+			assert!(System::events().contains(&EventRecord {
+				phase: Phase::ApplyExtrinsic(0),
+				event: Event::voting(voting::RawEvent::DecryptionExpired(1, 1))
+			}));
+			assert_eq!(Voting::pending_decryptions(1), 0);
+			assert_eq!(Voting::decryption_deadline(1), None);
+			assert_eq!(Voting::decrypting_votes(), vec![]);
+			assert_eq!(Voting::tally(1), Some(vec![(yes, 1), (no, 0)]));
+		});
+	}
+
+	#[test]
+	fn force_cancelled_decrypting_vote_should_return_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let public = get_test_key();
+			let voter = H256::from_low_u64_be(1);
+			let vote = generate_1p1v_public_binary_vote();
+			let (key, _) = threshold::deal(&[7; 32], &[[9; 32]], 3);
+			assert_ok!(Voting::set_threshold_key(system::RawOrigin::Root.into(), key.clone()));
+			assert_eq!(Ok(1), create_encrypted_vote(public, &vote.3));
+			Balances::set_reserved_balance(&public, 100);
+			<voting::CreationDeposits<Test>>::insert(1, (public, 100));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+			assert_ok!(cast_encrypted(voter, 1, &key, 1, vec![vote.3[0]]));
+			assert_ok!(advance_stage_as_initiator(public, 1));
+
+			assert_ok!(force_cancel_vote(1));
+			assert_eq!(Balances::reserved_balance(&public), 0);
+			assert_eq!(Balances::free_balance(&public), 100);
+			assert_eq!(Voting::decrypting_votes(), vec![]);
+			assert_eq!(Voting::decryption_deadline(1), None);
+		});
+	}
+
+	// Property tests feeding random sequences of commits, reveals and
+	// delegations to a coin weighted vote
+	mod properties {
+		use super::*;
+		use proptest::prelude::*;
+
+		#[derive(Clone, Debug)]
+		enum Action {
+			Commit(usize, usize),
+			Reveal(usize, usize, bool),
+			Delegate(usize, usize),
+			Advance,
+		}
+
+		fn action() -> impl Strategy<Value = Action> {
+			prop_oneof![
+				(0..6usize, 0..3usize).prop_map(|(who, outcome)| Action::Commit(who, outcome)),
+				(0..6usize, 0..3usize, any::<bool>())
+					.prop_map(|(who, outcome, with_secret)| Action::Reveal(who, outcome, with_secret)),
+				(0..6usize, 0..6usize).prop_map(|(who, to)| Action::Delegate(who, to)),
+				Just(Action::Advance),
+			]
+		}
+
+		// the running totals hold exactly the weight of the counted accounts
+		// and, while the vote is open, match a recount of the delegation graph
+		fn assert_totals_match_counted(vote_id: u64, users: &[H256]) {
+			let counted: Vec<(Ballot, u64)> = users.iter()
+				.filter_map(|who| Voting::contribution_of((vote_id, *who)))
+				.collect();
+			let counted_weight: u64 = counted.iter().map(|(_, w)| *w).sum();
+			let mut running: Vec<(Ballot, u64)> = Voting::ballot_totals(vote_id).into_iter()
+				.filter(|(_, w)| *w > 0)
+				.collect();
+			assert_eq!(running.iter().map(|(_, w)| *w).sum::<u64>(), counted_weight);
+			assert_eq!(Voting::turnout(vote_id), (counted.len() as u64, counted_weight));
+			if let Some(tally) = Voting::tally(vote_id) {
+				assert_eq!(tally.iter().map(|(_, w)| *w).sum::<u64>(), counted_weight);
+			}
+
+			if Voting::vote_records(vote_id).map_or(false, |r| r.data.stage == VoteStage::Voting) {
+				let mut fresh: Vec<(Ballot, u64)> = Voting::recount_from_graph(vote_id).unwrap_or_default()
+					.into_iter()
+					.filter(|(_, w)| *w > 0)
+					.collect();
+				running.sort();
+				fresh.sort();
+				assert_eq!(running, fresh);
+			}
+		}
+
+		proptest! {
+			#[test]
+			fn random_sequences_should_not_panic_and_keep_totals(
+				is_commit_reveal in any::<bool>(),
+				actions in prop::collection::vec(action(), 1..40)
+			) {
+				with_externalities(&mut new_test_ext(), || {
+					System::set_block_number(1);
+					let creator = get_test_key();
+					let users: Vec<H256> = (1..7).map(|v| H256::from_low_u64_be(v)).collect();
+					users.iter().enumerate()
+						.for_each(|(i, who)| Balances::set_free_balance(who, 10 * (i as u64 + 1)));
+					let (vote_type, _, _, outcomes) = generate_1p1v_public_binary_vote();
+					// the last choice is not an outcome of the vote
+					let choice = |i: usize| if i < outcomes.len() { outcomes[i] } else { [9; 32] };
+					assert_eq!(Ok(1), create_vote(creator, vote_type, is_commit_reveal, TallyType::OneCoin, &outcomes));
+
+					for action in actions {
+						let _ = match action {
+							Action::Commit(who, o) => {
+								let commitment = compute_commitment::<_, BlakeTwo256>(&users[who], &SECRET, &[choice(o)]);
+								commit(users[who], 1, commitment.into())
+							},
+							Action::Reveal(who, o, with_secret) =>
+								reveal(users[who], 1, vec![choice(o)], if with_secret { Some(SECRET) } else { None }),
+							Action::Delegate(who, to) => delegate_to(users[who], users[to]),
+							Action::Advance => advance_stage_as_initiator(creator, 1),
+						};
+						assert_totals_match_counted(1, &users);
+					}
+				});
+			}
+		}
+	}
 }
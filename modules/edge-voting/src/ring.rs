@@ -0,0 +1,167 @@
+// Copyright 2018 Commonwealth Labs, Inc.
+// This file is part of Edgeware.
+
+// Edgeware is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Edgeware is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Edgeware.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Linkable spontaneous anonymous group (LSAG) signatures over Ristretto.
+//!
+//! A signature proves that the signer holds the secret key of one member
+//! of a ring of public keys without revealing which one. Each signature
+//! carries a key image `x * H(scope ++ P)` which is the same for every
+//! signature made by one key in one scope, so the voting module can use
+//! the vote id as scope and reject a second ballot with a known image.
+
+use rstd::prelude::*;
+use codec::Encode;
+#[cfg(feature = "std")]
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::Sha512;
+
+/// A compressed Ristretto point, used for ring members and key images
+pub type PublicKey = [u8; 32];
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct RingSignature {
+	// Challenge for the first member of the ring
+	pub challenge: [u8; 32],
+	// One response scalar per ring member, in ring order
+	pub responses: Vec<[u8; 32]>,
+	// Key image linking all signatures by the same key in the same scope
+	pub key_image: PublicKey,
+}
+
+// hashes a ring member into a point whose discrete log nobody knows
+fn hash_to_point(scope: &[u8], member: &PublicKey) -> RistrettoPoint {
+	let mut buf = Vec::new();
+	buf.extend_from_slice(scope);
+	buf.extend_from_slice(member);
+	RistrettoPoint::hash_from_bytes::<Sha512>(&buf)
+}
+
+// derives the challenge for the next ring member
+fn challenge(
+	ring: &[PublicKey],
+	scope: &[u8],
+	message: &[u8],
+	l: &RistrettoPoint,
+	r: &RistrettoPoint
+) -> Scalar {
+	let mut buf = Vec::new();
+	buf.extend_from_slice(&ring.encode());
+	buf.extend_from_slice(scope);
+	buf.extend_from_slice(message);
+	buf.extend_from_slice(l.compress().as_bytes());
+	buf.extend_from_slice(r.compress().as_bytes());
+	Scalar::hash_from_bytes::<Sha512>(&buf)
+}
+
+/// Returns true if a ring member is a valid encoding of a curve point
+pub fn is_valid_key(key: &PublicKey) -> bool {
+	CompressedRistretto(*key).decompress().is_some()
+}
+
+/// Verifies that `signature` was made over `message` in `scope` by the
+/// holder of one of the keys in `ring`.
+pub fn verify(ring: &[PublicKey], scope: &[u8], message: &[u8], signature: &RingSignature) -> bool {
+	if ring.is_empty() || signature.responses.len() != ring.len() {
+		return false;
+	}
+
+	let image = match CompressedRistretto(signature.key_image).decompress() {
+		Some(image) => image,
+		None => return false,
+	};
+	let mut c = match Scalar::from_canonical_bytes(signature.challenge) {
+		Some(c) => c,
+		None => return false,
+	};
+
+	for (member, response) in ring.iter().zip(signature.responses.iter()) {
+		let point = match CompressedRistretto(*member).decompress() {
+			Some(point) => point,
+			None => return false,
+		};
+		let s = match Scalar::from_canonical_bytes(*response) {
+			Some(s) => s,
+			None => return false,
+		};
+		// L = s * G + c * P, R = s * H(P) + c * I
+		let l = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, &point, &s);
+		let r = s * hash_to_point(scope, member) + c * image;
+		c = challenge(ring, scope, message, &l, &r);
+	}
+
+	c.to_bytes() == signature.challenge
+}
+
+/// Derives the public key for a secret key
+#[cfg(feature = "std")]
+pub fn public_key(secret: &[u8; 32]) -> PublicKey {
+	(Scalar::from_bytes_mod_order(*secret) * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()
+}
+
+/// Signs `message` in `scope` as the member at `index` of `ring`. Nonces are
+/// derived from the secret and message, so no randomness source is needed.
+#[cfg(feature = "std")]
+pub fn sign(
+	ring: &[PublicKey],
+	index: usize,
+	secret: &[u8; 32],
+	scope: &[u8],
+	message: &[u8]
+) -> Option<RingSignature> {
+	let x = Scalar::from_bytes_mod_order(*secret);
+	if index >= ring.len() || public_key(secret) != ring[index] {
+		return None;
+	}
+
+	let nonce = |i: usize| {
+		let mut buf = Vec::new();
+		buf.extend_from_slice(secret);
+		buf.extend_from_slice(scope);
+		buf.extend_from_slice(message);
+		buf.extend_from_slice(&(i as u64).encode());
+		Scalar::hash_from_bytes::<Sha512>(&buf)
+	};
+
+	let n = ring.len();
+	let base = hash_to_point(scope, &ring[index]);
+	let image = x * base;
+	let alpha = nonce(n);
+	let mut responses = vec![Scalar::zero(); n];
+	let mut challenges = vec![Scalar::zero(); n];
+
+	// start the ring just after the signer and walk around to it
+	challenges[(index + 1) % n] = challenge(ring, scope, message, &(alpha * RISTRETTO_BASEPOINT_POINT), &(alpha * base));
+	let mut i = (index + 1) % n;
+	while i != index {
+		let point = CompressedRistretto(ring[i]).decompress()?;
+		responses[i] = nonce(i);
+		let l = RistrettoPoint::vartime_double_scalar_mul_basepoint(&challenges[i], &point, &responses[i]);
+		let r = responses[i] * hash_to_point(scope, &ring[i]) + challenges[i] * image;
+		challenges[(i + 1) % n] = challenge(ring, scope, message, &l, &r);
+		i = (i + 1) % n;
+	}
+	// close the ring with the signer's response
+	responses[index] = alpha - challenges[index] * x;
+
+	Some(RingSignature {
+		challenge: challenges[0].to_bytes(),
+		responses: responses.iter().map(|s| s.to_bytes()).collect(),
+		key_image: image.compress().to_bytes(),
+	})
+}
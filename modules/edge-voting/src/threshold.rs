@@ -0,0 +1,264 @@
+// Copyright 2018 Commonwealth Labs, Inc.
+// This file is part of Edgeware.
+
+// Edgeware is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Edgeware is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Edgeware.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Threshold ElGamal encryption over Ristretto.
+//!
+//! The authorities share a secret key `x` with Shamir's scheme: authority
+//! `i` holds `x_i = f(i)` for a polynomial `f` of degree `threshold - 1`
+//! with `f(0) = x`, and publishes `X_i = x_i * G`. A voter encrypts to
+//! `X = x * G` by picking `r`, publishing `R = r * G` and masking the
+//! ballot with a keystream derived from `r * X`. Authority `i` decrypts by
+//! publishing the share `x_i * R` with a proof that it used the same `x_i`
+//! as in `X_i`, and any `threshold` shares interpolate to `x * R = r * X`.
+//!
+//! The shares come from a trusted dealer, see `deal`, who knows `x` and so
+//! can decrypt every ballot on its own. There is no distributed key
+//! generation, and the runtime takes the key from its privileged origin.
+
+use rstd::prelude::*;
+use codec::Encode;
+#[cfg(feature = "std")]
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use sha2::{Digest, Sha512};
+use ring::PublicKey;
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ThresholdKey {
+	// Number of decryption shares needed to decrypt
+	pub threshold: u32,
+	// Key ballots are encrypted to
+	pub public: PublicKey,
+	// Verification key of each authority's share, authority i at index i - 1
+	pub shares: Vec<PublicKey>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct Ciphertext {
+	// Ephemeral key `r * G`
+	pub ephemeral: PublicKey,
+	// Plaintext masked by the keystream
+	pub body: Vec<u8>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct DecryptionShare {
+	// Index of the authority that made the share, from 1
+	pub index: u32,
+	// The authority's share of the decryption key, `x_i * R`
+	pub share: PublicKey,
+	// Challenge and response proving the share uses the authority's key
+	pub challenge: [u8; 32],
+	pub response: [u8; 32],
+}
+
+// the Lagrange coefficient of the point at `index` among `indices`, for
+// interpolating the polynomial at `at`
+fn lagrange(indices: &[u32], index: u32, at: u64) -> Scalar {
+	let at = Scalar::from(at);
+	let i = Scalar::from(index as u64);
+	indices.iter()
+		.filter(|j| **j != index)
+		.fold(Scalar::one(), |acc, j| {
+			let j = Scalar::from(*j as u64);
+			acc * (at - j) * (i - j).invert()
+		})
+}
+
+// interpolates the points at `indices` at `at`
+fn interpolate(indices: &[u32], points: &[RistrettoPoint], at: u64) -> RistrettoPoint {
+	indices.iter()
+		.zip(points.iter())
+		.fold(RistrettoPoint::identity(), |acc, (i, p)| acc + lagrange(indices, *i, at) * p)
+}
+
+// derives the challenge of a decryption share proof
+fn challenge(
+	verification: &PublicKey,
+	ephemeral: &PublicKey,
+	share: &PublicKey,
+	a: &RistrettoPoint,
+	b: &RistrettoPoint
+) -> Scalar {
+	let mut buf = Vec::new();
+	buf.extend_from_slice(verification);
+	buf.extend_from_slice(ephemeral);
+	buf.extend_from_slice(share);
+	buf.extend_from_slice(a.compress().as_bytes());
+	buf.extend_from_slice(b.compress().as_bytes());
+	Scalar::hash_from_bytes::<Sha512>(&buf)
+}
+
+// masks or unmasks `data` with the keystream of a shared point and context
+fn apply_keystream(shared: &RistrettoPoint, context: &[u8], data: &[u8]) -> Vec<u8> {
+	let shared = shared.compress();
+	data.chunks(64)
+		.enumerate()
+		.flat_map(|(block, chunk)| {
+			let mut buf = Vec::new();
+			buf.extend_from_slice(b"edge-voting:ballot-key");
+			buf.extend_from_slice(shared.as_bytes());
+			buf.extend_from_slice(context);
+			buf.extend_from_slice(&(block as u64).encode());
+			let stream = Sha512::digest(&buf);
+			chunk.iter().zip(stream.iter()).map(|(d, s)| d ^ s).collect::<Vec<u8>>()
+		})
+		.collect()
+}
+
+/// Returns true if every key is a valid point and the verification keys lie
+/// on a polynomial of degree `threshold - 1` passing through the public key.
+pub fn is_valid_key(key: &ThresholdKey) -> bool {
+	let t = key.threshold as usize;
+	if t == 0 || t > key.shares.len() {
+		return false;
+	}
+	let public = match CompressedRistretto(key.public).decompress() {
+		Some(public) => public,
+		None => return false,
+	};
+	let mut shares = Vec::new();
+	for share in key.shares.iter() {
+		match CompressedRistretto(*share).decompress() {
+			Some(point) => shares.push(point),
+			None => return false,
+		}
+	}
+
+	// the first `threshold` shares fix the polynomial, the rest must agree
+	let indices: Vec<u32> = (1..=key.threshold).collect();
+	if interpolate(&indices, &shares[..t], 0) != public {
+		return false;
+	}
+	shares.iter()
+		.enumerate()
+		.skip(t)
+		.all(|(i, share)| interpolate(&indices, &shares[..t], i as u64 + 1) == *share)
+}
+
+/// Verifies that `share` was made by the authority at its index from the
+/// ciphertext with `ephemeral` key, using the share `key` says it holds.
+pub fn verify_share(key: &ThresholdKey, ephemeral: &PublicKey, share: &DecryptionShare) -> bool {
+	if share.index == 0 || share.index as usize > key.shares.len() {
+		return false;
+	}
+	let verification = key.shares[share.index as usize - 1];
+	let points = (
+		CompressedRistretto(verification).decompress(),
+		CompressedRistretto(*ephemeral).decompress(),
+		CompressedRistretto(share.share).decompress(),
+	);
+	let (verification_point, ephemeral_point, share_point) = match points {
+		(Some(v), Some(e), Some(s)) => (v, e, s),
+		_ => return false,
+	};
+	let (c, s) = match (Scalar::from_canonical_bytes(share.challenge), Scalar::from_canonical_bytes(share.response)) {
+		(Some(c), Some(s)) => (c, s),
+		_ => return false,
+	};
+
+	// A = s * G + c * X_i, B = s * R + c * D
+	let a = RistrettoPoint::vartime_double_scalar_mul_basepoint(&c, &verification_point, &s);
+	let b = s * ephemeral_point + c * share_point;
+	challenge(&verification, ephemeral, &share.share, &a, &b) == c
+}
+
+/// Combines the first `threshold` shares with distinct indices to decrypt
+/// `ciphertext`. Shares must have been checked with `verify_share` already.
+/// Returns `None` if there are too few shares.
+pub fn decrypt(key: &ThresholdKey, ciphertext: &Ciphertext, shares: &[DecryptionShare], context: &[u8]) -> Option<Vec<u8>> {
+	let mut indices = Vec::new();
+	let mut points = Vec::new();
+	for share in shares.iter() {
+		if indices.len() == key.threshold as usize {
+			break;
+		}
+		if !indices.contains(&share.index) {
+			indices.push(share.index);
+			points.push(CompressedRistretto(share.share).decompress()?);
+		}
+	}
+	if indices.len() < key.threshold as usize {
+		return None;
+	}
+
+	let shared = interpolate(&indices, &points, 0);
+	Some(apply_keystream(&shared, context, &ciphertext.body))
+}
+
+/// Splits `secret` among `authorities` authorities with a trusted dealer,
+/// returning the key and each authority's secret share in index order. The
+/// threshold is one more than the number of `coefficients`.
+#[cfg(feature = "std")]
+pub fn deal(secret: &[u8; 32], coefficients: &[[u8; 32]], authorities: u32) -> (ThresholdKey, Vec<[u8; 32]>) {
+	let x = Scalar::from_bytes_mod_order(*secret);
+	let coefficients: Vec<Scalar> = coefficients.iter().map(|c| Scalar::from_bytes_mod_order(*c)).collect();
+	let secrets: Vec<Scalar> = (1..=authorities)
+		.map(|i| {
+			let i = Scalar::from(i as u64);
+			// Horner's rule, highest coefficient first
+			coefficients.iter().rev().fold(Scalar::zero(), |acc, c| (acc + c) * i) + x
+		})
+		.collect();
+
+	let key = ThresholdKey {
+		threshold: coefficients.len() as u32 + 1,
+		public: (x * RISTRETTO_BASEPOINT_POINT).compress().to_bytes(),
+		shares: secrets.iter().map(|s| (s * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()).collect(),
+	};
+	(key, secrets.iter().map(|s| s.to_bytes()).collect())
+}
+
+/// Encrypts `plaintext` to `key`, bound to `context`, using the ephemeral
+/// secret `nonce`. The nonce must be random and never reused.
+#[cfg(feature = "std")]
+pub fn encrypt(key: &ThresholdKey, nonce: &[u8; 32], context: &[u8], plaintext: &[u8]) -> Option<Ciphertext> {
+	let public = CompressedRistretto(key.public).decompress()?;
+	let r = Scalar::from_bytes_mod_order(*nonce);
+	Some(Ciphertext {
+		ephemeral: (r * RISTRETTO_BASEPOINT_POINT).compress().to_bytes(),
+		body: apply_keystream(&(r * public), context, plaintext),
+	})
+}
+
+/// Makes the decryption share of the authority at `index`, holding the
+/// secret share `secret`, for the ciphertext with `ephemeral` key. The proof
+/// nonce is derived from the secret and ephemeral key.
+#[cfg(feature = "std")]
+pub fn decryption_share(index: u32, secret: &[u8; 32], ephemeral: &PublicKey) -> Option<DecryptionShare> {
+	let x = Scalar::from_bytes_mod_order(*secret);
+	let ephemeral_point = CompressedRistretto(*ephemeral).decompress()?;
+	let verification = (x * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+	let share = (x * ephemeral_point).compress().to_bytes();
+
+	let mut buf = Vec::new();
+	buf.extend_from_slice(secret);
+	buf.extend_from_slice(ephemeral);
+	let k = Scalar::hash_from_bytes::<Sha512>(&buf);
+	let c = challenge(&verification, ephemeral, &share, &(k * RISTRETTO_BASEPOINT_POINT), &(k * ephemeral_point));
+
+	Some(DecryptionShare {
+		index: index,
+		share: share,
+		challenge: c.to_bytes(),
+		response: (k - c * x).to_bytes(),
+	})
+}
@@ -32,20 +32,26 @@ extern crate sr_primitives as runtime_primitives;
 extern crate sr_io as runtime_io;
 extern crate srml_balances as balances;
 extern crate srml_system as system;
+extern crate srml_timestamp as timestamp;
 extern crate edge_delegation as delegation;
 
 use rstd::prelude::*;
 use rstd::result;
-use system::ensure_signed;
-use runtime_support::{StorageValue, StorageMap};
+use system::{ensure_signed, ensure_inherent};
+use runtime_support::{StorageValue, StorageMap, Parameter};
 use runtime_support::dispatch::Result;
 use runtime_primitives::traits::Hash;
 use runtime_primitives::traits::{Zero, One};
-use runtime_primitives::traits::{CheckedAdd};
-use codec::Encode;
+use runtime_primitives::traits::{CheckedMul, Saturating, As, IntegerSquareRoot, EnsureOrigin, Verify};
+use runtime_primitives::transaction_validity::{TransactionValidity, TransactionLongevity};
+use codec::{Encode, Decode};
+use ring::{self, PublicKey, RingSignature};
+use threshold::{self, ThresholdKey, Ciphertext, DecryptionShare};
 
 /// A potential outcome of a vote, with 2^32 possible options
 pub type VoteOutcome = [u8; 32];
+/// An ordered list of outcomes submitted by a voter, most preferred first
+pub type Ballot = Vec<VoteOutcome>;
 pub type Tally<Balance> = Option<Vec<(VoteOutcome, Balance)>>;
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -59,6 +65,14 @@ pub enum VoteStage {
 	Voting,
 	// Completed voting stage, no more votes allowed
 	Completed,
+	// Withdrawn before completing, no more votes allowed and no tally
+	Cancelled,
+	// Voting over on an encrypted vote, waiting for the authorities to
+	// decrypt its ballots before completing
+	Decrypting,
+	// Voting over, waiting for the delegators already queued to be counted
+	// before completing
+	Tallying,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -66,15 +80,24 @@ pub enum VoteStage {
 pub enum VoteType {
 	// Binary decision vote, i.e. 2 outcomes
 	Binary,
-	// Multi option decision vote, i.e. > 2 possible outcomes
-	// TODO: Add support for this type
+	// Multi option decision vote, i.e. > 2 possible outcomes,
+	// tallied by instant-runoff over ranked ballots
 	MultiOption,
-	// Anonymous vote using ring signatures
-	// TODO: Add support for this type
+	// Anonymous vote using linkable ring signatures over a fixed ring of keys
 	AnonymousRing,
-	// Anonymous vote using merkle tree accumulators
-	// TODO: Add support for this type
-	AnonymousMerkle,
+	// Vote open to the holders of the commitments in a merkle tree, each
+	// revealing a one-time nullifier. Not anonymous: a reveal exposes its
+	// leaf to whoever knows which voter holds it
+	MerkleMembership,
+	// Approval vote, i.e. >= 2 possible outcomes, where every outcome on a
+	// ballot receives the voter's full weight
+	Approval,
+	// Multi winner election over approval ballots, filling the given number
+	// of seats by sequential Phragmen over coin weights
+	Committee(u32),
+	// Numeric vote where each ballot holds a single value in the range
+	// (min, max, step), tallied by weighted median
+	Scalar(u64, u64, u64),
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -84,11 +107,15 @@ pub enum TallyType {
 	OnePerson,
 	// 1 coin 1 vote, i.e. by balances
 	OneCoin,
+	// square root of balance, i.e. quadratic voting
+	Quadratic,
+	// balance multiplied by one plus the periods the voter locks it for
+	Conviction,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, PartialEq)]
-pub struct VoteData<AccountId> {
+pub struct VoteData<AccountId, Balance> {
 	// creator of vote
 	pub initiator: AccountId,
 	// Stage of the vote
@@ -99,158 +126,1524 @@ pub struct VoteData<AccountId> {
 	pub tally_type: TallyType,
 	// Flag for commit/reveal voting scheme
 	pub is_commit_reveal: bool,
+	// Root of the merkle tree of voter commitments, for merkle membership votes
+	pub merkle_root: Option<[u8; 32]>,
+	// Quorum and threshold deciding whether the vote passes
+	pub rules: VoteRules<Balance>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum Quorum {
+	// Counted weight must reach this percentage of total issuance
+	PercentOfIssuance(u32),
+	// Counted accounts must reach this percentage of the given number of eligible accounts
+	PercentOfAccounts(u32, u64),
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum Threshold<Balance> {
+	// The winning outcome needs more than half of the counted weight
+	SimpleMajority,
+	// The winning outcome needs at least this percentage of the counted weight
+	Supermajority(u32),
+	// The winning outcome needs at least this much weight
+	Absolute(Balance),
+}
+
+impl<Balance> Default for Threshold<Balance> {
+	fn default() -> Self {
+		Threshold::SimpleMajority
+	}
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum VoterPolicy<Balance> {
+	// Any account may vote
+	Anyone,
+	// Only accounts the initiator added to the vote's allowlist
+	Allowlist,
+	// Only accounts holding at least this free balance
+	MinBalance(Balance),
+	// Only accounts approved by the runtime's eligibility provider
+	External,
+}
+
+impl<Balance> Default for VoterPolicy<Balance> {
+	fn default() -> Self {
+		VoterPolicy::Anyone
+	}
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, Default)]
+pub struct VoteRules<Balance> {
+	// Minimum turnout, if any
+	pub quorum: Option<Quorum>,
+	// Support the winning outcome needs
+	pub threshold: Threshold<Balance>,
+	// Accounts that may vote and be counted through delegation
+	pub eligibility: VoterPolicy<Balance>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum VoteResult {
+	// The outcome met the threshold
+	Passed(VoteOutcome),
+	// Quorum was reached but no outcome met the threshold
+	Failed,
+	// Too few voters took part
+	NoQuorum,
+	// The committee election filled this many seats
+	Elected(u32),
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum VoteSchedule<BlockNumber, Moment> {
+	// (end of commit stage, end of voting stage) as block numbers
+	Blocks(Option<BlockNumber>, BlockNumber),
+	// (end of commit stage, end of voting stage) as timestamps
+	Moments(Option<Moment>, Moment),
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct MerkleProof {
+	// Position of the voter's commitment among the tree leaves
+	pub index: u64,
+	// Sibling hashes from the leaf up to the root
+	pub path: Vec<[u8; 32]>,
 }
 
 #[cfg_attr(feature = "std", derive(Debug))]
 #[derive(Encode, Decode, PartialEq)]
-pub struct VoteRecord<AccountId> {
+pub struct VoteRecord<AccountId, Balance> {
 	// Identifier of the vote
 	pub id: u64,
-	// Vote commitments
-	pub commitments: Vec<(AccountId, VoteOutcome)>,
-	// Vote reveals
-	pub reveals: Vec<(AccountId, VoteOutcome)>,
 	// Vote data record
-	pub data: VoteData<AccountId>,
+	pub data: VoteData<AccountId, Balance>,
 	// Vote outcomes
 	pub outcomes: Vec<VoteOutcome>,
 }
 
-pub trait Trait: balances::Trait + delegation::Trait {
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub struct PrunedVote<Balance, Hash, Moment> {
+	// Final tally, none if the vote was cancelled
+	pub tally: Tally<Balance>,
+	// Hash chain of the revealed ballots, public ones tagged by voter in the
+	// order they were first revealed, then unsigned ones tagged by key image
+	// or nullifier, each link hashing the previous link, the tag and the ballot
+	pub ballots_hash: Hash,
+	// Time each stage of the vote began
+	pub stage_times: Vec<(VoteStage, Moment)>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub struct CommitteeResult<AccountId, Balance> {
+	// Elected outcomes, in the order they were elected
+	pub elected: Vec<VoteOutcome>,
+	// Each counted account's weight, split across the elected outcomes it
+	// approved in proportion to its load on them
+	pub distribution: Vec<(AccountId, Vec<(VoteOutcome, Balance)>)>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct RunoffRound<Balance> {
+	// Weight counted towards each outcome, eliminated outcomes at zero
+	pub counts: Vec<(VoteOutcome, Balance)>,
+	// Outcomes eliminated after this round, none after the final round
+	pub eliminated: Vec<VoteOutcome>,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq)]
+pub enum PruneStep {
+	// Public ballots and their voters, from this index of the voter list
+	Voters(u64),
+	// Unsigned ballots and their key images or nullifiers, from this index
+	Anonymous(u64),
+	// Commitments, from this index of the committer list
+	Committers(u64),
+	// Counted weights and pending locks, from this index of the contributor list
+	Contributors(u64),
+	// Allowlist entries, from this index of the allowlisted accounts
+	Allowlist(u64),
+	// Encrypted ballots and decryption shares, from this index of the encrypted voters
+	Encrypted(u64),
+	// The remaining per vote entries and the record itself
+	Record,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq)]
+pub struct PruneCursor<Hash> {
+	// Identifier of the vote being pruned
+	pub vote_id: u64,
+	// Storage of the vote still to remove
+	pub step: PruneStep,
+	// Hash chain of the ballots removed so far
+	pub ballots_hash: Hash,
+}
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub enum Recount<AccountId> {
+	// The delegators of an account on a vote, from this index of its delegator list
+	Delegators(u64, AccountId, u32),
+	// An account whose delegation changed, on the active votes after this id
+	Delegation(AccountId, u64),
+	// The lock periods of the voters of a completed conviction vote, from
+	// this index of its voters
+	Locks(u64, u64),
+}
+
+// The most queued accounts recounted by a reveal or delegation change
+const MAX_RECOUNTS_PER_CALL: usize = 64;
+
+// The most queued accounts recounted in a single block
+const MAX_RECOUNTS_PER_BLOCK: usize = 256;
+
+// The fixed point unit of Phragmen loads
+const PHRAGMEN_SCALE: u128 = 1 << 64;
+
+// The most storage entries of finished votes removed in a single block
+const MAX_PRUNED_REMOVALS_PER_BLOCK: usize = 256;
+
+// The storage entries removed along with a pruned vote's record
+const RECORD_REMOVALS: usize = 17;
+
+// The most relayed ballots accepted in a single call
+const MAX_SIGNED_BALLOTS: usize = 64;
+
+// Validity code of unsigned calls the transaction pool should drop
+const INVALID_UNSIGNED_CALL: i8 = -30;
+
+// Prefix of the pool tag provided by an anonymous reveal
+const UNSIGNED_REVEAL_TAG: &[u8] = b"edge-voting:anonymous";
+
+// Prefix of the payload signed for a relayed ballot
+const SIGNED_BALLOT_TAG: &[u8] = b"edge-voting:ballot";
+
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct SignedBallot<AccountId, Signature> {
+	// Account the ballot is revealed for
+	pub signer: AccountId,
+	// Vote the ballot is cast on
+	pub vote_id: u64,
+	// Ballot, as it would be passed to `reveal`
+	pub ballot: Ballot,
+	// Next relayed ballot nonce of the signer
+	pub nonce: u64,
+	// Signature by the signer over `signed_ballot_payload`
+	pub signature: Signature,
+}
+
+// The layout of vote records before storage version 1, which held their
+// commitments and reveals inline, revealed a single outcome per voter and
+// had no merkle root or rules. Later stages, types and tally types were
+// appended, so the old ones decode as before.
+#[derive(Decode)]
+struct LegacyVoteRecord<AccountId> {
+	id: u64,
+	commitments: Vec<(AccountId, VoteOutcome)>,
+	reveals: Vec<(AccountId, VoteOutcome)>,
+	data: LegacyVoteData<AccountId>,
+	outcomes: Vec<VoteOutcome>,
+}
+
+#[derive(Decode)]
+struct LegacyVoteData<AccountId> {
+	initiator: AccountId,
+	stage: VoteStage,
+	vote_type: VoteType,
+	tally_type: TallyType,
+	is_commit_reveal: bool,
+}
+
+/// Approves voters on votes whose policy defers to the runtime, for example
+/// accounts with a verified identity
+pub trait VoterEligibility<AccountId> {
+	fn can_vote(vote_id: u64, who: &AccountId) -> bool;
+}
+
+impl<AccountId> VoterEligibility<AccountId> for () {
+	fn can_vote(_vote_id: u64, _who: &AccountId) -> bool {
+		false
+	}
+}
+
+/// Receives the commitment bonds slashed from voters who never revealed and
+/// the creation deposits of force-cancelled votes
+pub trait OnBondSlashed<Balance> {
+	fn on_bond_slashed(amount: Balance);
+}
+
+impl<Balance> OnBondSlashed<Balance> for () {
+	fn on_bond_slashed(_amount: Balance) {}
+}
+
+/// The bytes a commitment hashes: the SCALE encoded account, the 32 bytes of
+/// the secret, then the 32 bytes of each outcome of the ballot in order. A
+/// ballot of one outcome hashes the same bytes as commitments always have.
+pub fn commitment_preimage<AccountId: Encode>(account: &AccountId, secret: &VoteOutcome, ballot: &[VoteOutcome]) -> Vec<u8> {
+	let mut buf = Vec::new();
+	buf.extend_from_slice(&account.encode());
+	buf.extend_from_slice(secret);
+	ballot.iter().for_each(|v| buf.extend_from_slice(v));
+	buf
+}
+
+/// Computes the commitment to submit with `commit` for revealing `ballot`
+/// later with `secret`. This is `H::hash_of` of the preimage, so the hashed
+/// bytes are the preimage prefixed by its compact encoded length.
+#[cfg(feature = "std")]
+pub fn compute_commitment<AccountId: Encode, H: Hash>(account: &AccountId, secret: &VoteOutcome, ballot: &[VoteOutcome]) -> H::Output {
+	H::hash_of(&commitment_preimage(account, secret, ballot))
+}
+
+/// The bytes signed for a relayed ballot: the `edge-voting:ballot` tag, then
+/// the SCALE encoded genesis hash of the chain, vote id, ballot and nonce. The
+/// genesis hash keeps a ballot signed for one chain from counting on another.
+pub fn signed_ballot_payload<Hash: Encode>(genesis_hash: &Hash, vote_id: u64, ballot: &[VoteOutcome], nonce: u64) -> Vec<u8> {
+	let mut buf = SIGNED_BALLOT_TAG.to_vec();
+	buf.extend_from_slice(&(genesis_hash, vote_id, ballot.to_vec(), nonce).encode());
+	buf
+}
+
+/// Encodes a value for a scalar vote ballot, big-endian in the last 8 bytes
+/// so that encoded values sort in numeric order.
+pub fn scalar_outcome(value: u64) -> VoteOutcome {
+	let mut outcome = [0u8; 32];
+	for i in 0..8 {
+		outcome[31 - i] = (value >> (8 * i)) as u8;
+	}
+	outcome
+}
+
+/// Decodes a scalar vote ballot value, if the outcome encodes one
+pub fn scalar_value(outcome: &VoteOutcome) -> Option<u64> {
+	if outcome[..24].iter().any(|b| *b != 0) {
+		return None;
+	}
+	Some(outcome[24..].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64))
+}
+
+/// Notified whenever a vote completes, with its final result, or is cancelled
+pub trait OnVoteCompleted {
+	fn on_vote_completed(vote_id: u64, result: VoteResult);
+	fn on_vote_cancelled(vote_id: u64);
+}
+
+impl OnVoteCompleted for () {
+	fn on_vote_completed(_vote_id: u64, _result: VoteResult) {}
+	fn on_vote_cancelled(_vote_id: u64) {}
+}
+
+pub trait Trait: balances::Trait + delegation::Trait + timestamp::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+	/// Origin allowed to cancel any vote that has not completed
+	type CancelOrigin: EnsureOrigin<Self::Origin>;
+	/// Provider approving voters on votes with an external policy
+	type Eligibility: VoterEligibility<Self::AccountId>;
+	/// Destination of slashed commitment bonds and creation deposits
+	type OnBondSlashed: OnBondSlashed<Self::Balance>;
+	/// Handler notified when a vote completes or is cancelled, such as the module that created it
+	type OnVoteCompleted: OnVoteCompleted;
+	/// Signature over relayed ballots, verified against the signing account
+	type Signature: Parameter + Verify<Signer = Self::AccountId>;
+	/// Origin allowed to set the threshold key held by the authorities
+	type KeyOrigin: EnsureOrigin<Self::Origin>;
+	/// Origin allowed to set the deposits, bonds and periods votes use
+	type ParameterOrigin: EnsureOrigin<Self::Origin>;
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event<T>() = default;
 
+		/// Commit to a ballot on a commit-reveal vote. The first commitment by
+		/// an account reserves the commitment bond, which is returned when the
+		/// account reveals and slashed if it never does.
 		pub fn commit(origin, vote_id: u64, commit: VoteOutcome) -> Result {
 			let _sender = ensure_signed(origin)?;
-			let mut record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
 			ensure!(record.data.is_commit_reveal, "Commitments are not configured for this vote");
 			ensure!(record.data.stage == VoteStage::Commit, "Vote is not in commit stage");
-			// TODO: Allow changing of commits before commit stage ends
-			ensure!(!record.commitments.iter().any(|c| &c.0 == &_sender), "Duplicate commits are not allowed");
+			ensure!(Self::is_eligible(&record, &_sender), "Sender is not eligible to vote");
 
-			// Add commitment to record
-			record.commitments.push((_sender.clone(), commit));
-			let id = record.id;
-			<VoteRecords<T>>::insert(id, record);
-			Self::deposit_event(RawEvent::VoteCommitted(id, _sender));
+			// Bond the first commitment, replacing one keeps the bond in place
+			let bond = Self::commit_bond();
+			if !<Commitments<T>>::exists((record.id, _sender.clone())) && !bond.is_zero() {
+				<balances::Module<T>>::reserve(&_sender, bond)?;
+				<CommitBonds<T>>::insert((record.id, _sender.clone()), bond);
+			}
+
+			// Add commitment, replacing any earlier one by the sender
+			Self::insert_commitment(record.id, &_sender, commit);
+			Self::deposit_event(RawEvent::VoteCommitted(record.id, _sender));
 			Ok(())
 		}
 
-		pub fn reveal(origin, vote_id: u64, vote: VoteOutcome, secret: Option<VoteOutcome>) -> Result {
+		/// Reveal a ballot. Binary votes take a single outcome, multi option
+		/// votes take outcomes ranked from most to least preferred, approval
+		/// votes take the set of approved outcomes and scalar votes take a
+		/// single value encoded by `scalar_outcome`. Revealing
+		/// again before voting ends replaces the earlier ballot. On conviction
+		/// votes, `conviction` is the number of lock periods the voter's balance
+		/// stays locked for after the vote completes; it must be 0 otherwise.
+		pub fn reveal(origin, vote_id: u64, vote: Ballot, secret: Option<VoteOutcome>, conviction: u8) -> Result {
 			let _sender = ensure_signed(origin)?;
-			let mut record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			Self::do_reveal(_sender, vote_id, vote, secret, conviction)
+		}
+
+		/// Count an account on an open coin weighted vote with what its balance
+		/// is worth now. Coins an account transfers or reserves are settled on
+		/// its votes automatically; anyone may call this once its balance grew,
+		/// or dropped otherwise, e.g. by a slash.
+		pub fn refresh_weight(origin, vote_id: u64, who: T::AccountId) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
 			ensure!(record.data.stage == VoteStage::Voting, "Vote is not in voting stage");
-			// Check vote is for a valid outcome
-			ensure!(record.outcomes.iter().any(|o| o == &vote), "Vote type must be binary");
-			// TODO: Allow changing of votes
-			ensure!(!record.reveals.iter().any(|c| &c.0 == &_sender), "Duplicate votes are not allowed");
-
-			// Ensure voter committed
-			if record.data.is_commit_reveal {
-				ensure!(record.commitments.iter().any(|c| &c.0 == &_sender), "Sender already committed");
-				let commit: (T::AccountId, VoteOutcome) = record.commitments
-					.iter()
-					.find(|c| &c.0 == &_sender)
-					.unwrap()
-					.clone();
-
-				let mut buf = Vec::new();
-				buf.extend_from_slice(&_sender.encode());
-				buf.extend_from_slice(&secret.unwrap().encode());
-				buf.extend_from_slice(&vote);
-				let hash = T::Hashing::hash_of(&buf);
-				ensure!(hash.encode() == commit.1.encode(), "Commitments do not match");
+			// coins that left other voters are no longer counted for them
+			Self::settle_balance_moves();
+			let (ballot, counted) = Self::contribution_of((vote_id, who.clone())).ok_or("Account is not counted on this vote")?;
+			let weight = Self::weight_of(&record, &who);
+			ensure!(weight != counted, "Counted weight is already up to date");
+
+			Self::remove_from_totals(vote_id, &ballot, counted);
+			Self::add_to_totals(vote_id, &ballot, weight);
+			<Contributions<T>>::insert((vote_id, who.clone()), (ballot, weight));
+			Self::deposit_event(RawEvent::WeightRefreshed(vote_id, who, weight));
+			Ok(())
+		}
+
+		/// Submit ballots signed off-chain by their voters, who then need no
+		/// funds or extrinsics of their own. Each ballot must be signed over
+		/// `signed_ballot_payload` with the signer's next nonce, and is revealed
+		/// for the signer as `reveal` would without a secret or conviction. A
+		/// verified ballot uses up its nonce even if the reveal fails, and
+		/// failed ballots are skipped so the rest of the batch still counts.
+		pub fn submit_signed_ballots(origin, ballots: Vec<SignedBallot<T::AccountId, T::Signature>>) -> Result {
+			let _sender = ensure_signed(origin)?;
+			ensure!(!ballots.is_empty(), "No signed ballots to submit");
+			ensure!(ballots.len() <= MAX_SIGNED_BALLOTS, "Too many signed ballots");
+			for ballot in ballots.into_iter() {
+				let (vote_id, signer, nonce) = (ballot.vote_id, ballot.signer.clone(), ballot.nonce);
+				if Self::reveal_signed(ballot).is_err() {
+					Self::deposit_event(RawEvent::SignedBallotRejected(vote_id, signer, nonce));
+				}
 			}
+			Ok(())
+		}
 
-			let id = record.id;
-			record.reveals.push((_sender.clone(), vote));
-			<VoteRecords<T>>::insert(id, record);
-			Self::deposit_event(RawEvent::VoteRevealed(id, _sender, vote));
+		/// Cast an encrypted ballot on an encrypted vote, replacing any earlier
+		/// one by the sender. The ciphertext holds the SCALE encoded ballot,
+		/// encrypted to the vote's threshold key with the encoded
+		/// `(vote_id, sender)` as context, and is revealed for the sender once
+		/// the authorities decrypt it after voting ends.
+		pub fn cast_encrypted(origin, vote_id: u64, ciphertext: Ciphertext) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			ensure!(<VoteKeys<T>>::exists(vote_id), "Vote does not take encrypted ballots");
+			ensure!(record.data.stage == VoteStage::Voting, "Vote is not in voting stage");
+			ensure!(Self::is_eligible(&record, &_sender), "Sender is not eligible to vote");
+			ensure!(ring::is_valid_key(&ciphertext.ephemeral), "Invalid ciphertext");
+			// room for a ballot of every outcome of the vote, or one scalar
+			// value, and its length prefix
+			let outcomes = rstd::cmp::max(record.outcomes.len(), 1);
+			ensure!(ciphertext.body.len() <= 4 + 32 * outcomes, "Ciphertext is too long");
+
+			if !<Ciphertexts<T>>::exists((vote_id, _sender.clone())) {
+				let index = Self::ciphertext_count(vote_id);
+				<EncryptedVoters<T>>::insert((vote_id, index), _sender.clone());
+				<CiphertextCount<T>>::insert(vote_id, index + 1);
+				<PendingDecryptions<T>>::mutate(vote_id, |n| *n += 1);
+			}
+			<Ciphertexts<T>>::insert((vote_id, _sender.clone()), ciphertext);
+			Self::deposit_event(RawEvent::EncryptedVoteCast(vote_id, _sender));
 			Ok(())
 		}
 
+		/// Submit decryption shares for the encrypted ballots of a vote that is
+		/// decrypting, each paired with the voter whose ballot it decrypts. Any
+		/// account may submit shares, as each proves it was made with one of
+		/// the authorities' key shares. A ballot is revealed for its voter once
+		/// enough shares are in, or discarded if it does not decrypt to a valid
+		/// ballot, and the vote completes once every ballot is decrypted.
+		pub fn submit_decryption_shares(origin, vote_id: u64, shares: Vec<(T::AccountId, DecryptionShare)>) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			ensure!(record.data.stage == VoteStage::Decrypting, "Vote is not in decrypting stage");
+			let key = Self::vote_key(vote_id).ok_or("Vote does not take encrypted ballots")?;
+			// check every share before storing any, ignoring late shares for
+			// ballots that were already decrypted
+			for (voter, share) in shares.iter() {
+				if let Some(ciphertext) = Self::ciphertext_of((vote_id, voter.clone())) {
+					ensure!(threshold::verify_share(&key, &ciphertext.ephemeral, share), "Invalid decryption share");
+				}
+			}
+
+			for (voter, share) in shares.into_iter() {
+				let ciphertext = match Self::ciphertext_of((vote_id, voter.clone())) {
+					Some(ciphertext) => ciphertext,
+					None => continue,
+				};
+				let mut received = Self::decryption_shares_of((vote_id, voter.clone()));
+				if received.iter().any(|s| s.index == share.index) {
+					continue;
+				}
+				received.push(share);
+				if received.len() < key.threshold as usize {
+					<DecryptionShares<T>>::insert((vote_id, voter), received);
+				} else {
+					Self::decrypt_ballot(&record, &key, &voter, &ciphertext, &received);
+				}
+			}
+
+			Self::process_recounts(MAX_RECOUNTS_PER_CALL);
+			if Self::pending_decryptions(vote_id) == 0 && Self::pending_recounts(vote_id) == 0 {
+				Self::advance_stage(vote_id)?;
+			}
+			Ok(())
+		}
+
+		/// Set the threshold key held by the current authorities, from the
+		/// privileged origin. Encrypted votes created afterwards use this key.
+		///
+		/// The key is dealt off-chain by a trusted dealer, as with
+		/// `threshold::deal`, who learns the whole secret key. Encrypted ballots
+		/// stay secret only while the dealer has discarded it and fewer than
+		/// `threshold` share holders collude. Nothing ties the shares to the
+		/// session authorities, so the key must be dealt and set again
+		/// whenever the authorities change.
+		pub fn set_threshold_key(origin, key: ThresholdKey) -> Result {
+			T::KeyOrigin::ensure_origin(origin)?;
+			ensure!(threshold::is_valid_key(&key), "Invalid threshold key");
+			let public = key.public;
+			<AuthorityKey<T>>::put(key);
+			Self::deposit_event(RawEvent::ThresholdKeyChanged(public));
+			Ok(())
+		}
+
+		/// Set the deposits, bonds and periods votes are created and counted
+		/// with, from the privileged origin. On chains that started before
+		/// they existed they are zero until set here.
+		pub fn set_vote_parameters(
+			origin,
+			creation_deposit: T::Balance,
+			max_outcomes: u32,
+			commit_bond: T::Balance,
+			lock_period: T::BlockNumber,
+			max_lock_periods: u8,
+			retention_period: T::BlockNumber,
+			decryption_period: T::BlockNumber
+		) -> Result {
+			T::ParameterOrigin::ensure_origin(origin)?;
+			ensure!(max_outcomes >= 2, "Votes must be allowed at least two outcomes");
+			<CreationDeposit<T>>::put(creation_deposit);
+			<MaxOutcomes<T>>::put(max_outcomes);
+			<CommitBond<T>>::put(commit_bond);
+			<LockPeriod<T>>::put(lock_period);
+			<MaxLockPeriods<T>>::put(max_lock_periods);
+			<RetentionPeriod<T>>::put(retention_period);
+			<DecryptionPeriod<T>>::put(decryption_period);
+			Ok(())
+		}
+
+		/// Reveal a ballot on an anonymous ring vote. Submitted unsigned, the
+		/// ballot is authorised by a linkable ring signature over the vote's ring
+		/// and enters the transaction pool through `validate_unsigned`.
+		pub fn reveal_anonymous(origin, vote_id: u64, vote: Ballot, signature: RingSignature) -> Result {
+			ensure_inherent(origin)?;
+			Self::check_ring_reveal(vote_id, &vote, &signature)?;
+
+			<KeyImages<T>>::insert((vote_id, signature.key_image), true);
+			<AnonymousReveals<T>>::mutate(vote_id, |reveals| reveals.push((signature.key_image, vote.clone())));
+			Self::add_to_totals(vote_id, &vote, One::one());
+			Self::deposit_event(RawEvent::AnonymousVoteRevealed(vote_id, signature.key_image, vote));
+			Ok(())
+		}
+
+		/// Reveal a ballot on a merkle membership vote. Submitted unsigned, the
+		/// ballot carries the voter's nullifier, a one-time public key whose hash
+		/// is a leaf of the vote's commitment tree, a proof of that membership
+		/// and a signature over the ballot by the nullifier's key, so a pending
+		/// reveal cannot be replayed with another ballot.
+		pub fn reveal_merkle(
+			origin,
+			vote_id: u64,
+			vote: Ballot,
+			nullifier: [u8; 32],
+			proof: MerkleProof,
+			signature: RingSignature
+		) -> Result {
+			ensure_inherent(origin)?;
+			Self::check_merkle_reveal(vote_id, &vote, &nullifier, &proof, &signature)?;
+
+			<Nullifiers<T>>::insert((vote_id, nullifier), true);
+			<AnonymousReveals<T>>::mutate(vote_id, |reveals| reveals.push((nullifier, vote.clone())));
+			Self::add_to_totals(vote_id, &vote, One::one());
+			Self::deposit_event(RawEvent::AnonymousVoteRevealed(vote_id, nullifier, vote));
+			Ok(())
+		}
+
+		/// Create a vote as a signed account, reserving the creation deposit
+		/// until the vote completes. The initiator advances unscheduled votes.
+		pub fn create_vote(
+			origin,
+			vote_type: VoteType,
+			is_commit_reveal: bool,
+			tally_type: TallyType,
+			outcomes: Vec<VoteOutcome>,
+			schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>,
+			rules: VoteRules<T::Balance>
+		) -> Result {
+			let _sender = ensure_signed(origin)?;
+			ensure!(outcomes.len() <= Self::max_outcomes() as usize, "Too many vote outcomes");
+
+			let deposit = Self::creation_deposit();
+			if !deposit.is_zero() {
+				<balances::Module<T>>::reserve(&_sender, deposit)?;
+			}
+			match Self::do_create_vote(_sender.clone(), vote_type, is_commit_reveal, tally_type, outcomes, schedule, rules) {
+				Ok(id) => {
+					if !deposit.is_zero() {
+						<CreationDeposits<T>>::insert(id, (_sender, deposit));
+					}
+					Ok(())
+				},
+				Err(err) => {
+					<balances::Module<T>>::unreserve(&_sender, deposit);
+					Err(err)
+				},
+			}
+		}
+
 		pub fn advance_stage_as_initiator(origin, vote_id: u64) -> Result {
 			let _sender = ensure_signed(origin)?;
 			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
 			ensure!(record.data.initiator == _sender, "Invalid advance attempt by non-owner");
+			ensure!(!<VoteSchedules<T>>::exists(vote_id), "Scheduled votes advance automatically");
 			return Self::advance_stage(vote_id);
 		}
+
+		/// Add accounts to the allowlist of a vote with an allowlist policy, as
+		/// its initiator, any time before the vote finishes.
+		pub fn allow_voters(origin, vote_id: u64, voters: Vec<T::AccountId>) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			ensure!(record.data.initiator == _sender, "Invalid allowlist attempt by non-owner");
+			ensure!(record.data.rules.eligibility == VoterPolicy::Allowlist, "Vote does not use an allowlist");
+			ensure!(Self::is_open(record.data.stage), "Vote is already finished");
+			for voter in voters.iter() {
+				if !Self::is_allowed((vote_id, voter.clone())) {
+					<Allowlisted<T>>::mutate(vote_id, |allowed| allowed.push(voter.clone()));
+				}
+				<Allowlist<T>>::insert((vote_id, voter.clone()), true);
+				// accounts delegating to a voter start counting right away
+				if record.data.stage == VoteStage::Voting {
+					Self::recount(&record, voter);
+				}
+			}
+			Ok(())
+		}
+
+		/// Cancel a vote as its initiator, before voting begins.
+		pub fn cancel_vote(origin, vote_id: u64) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			ensure!(record.data.initiator == _sender, "Invalid cancel attempt by non-owner");
+			ensure!(
+				record.data.stage == VoteStage::PreVoting || record.data.stage == VoteStage::Commit,
+				"Vote has already begun"
+			);
+			Self::do_cancel_vote(record)?;
+			Self::return_creation_deposit(vote_id);
+			Ok(())
+		}
+
+		/// Cancel any vote that has not completed, from the privileged origin.
+		/// The creation deposit of the vote, if any, is slashed, unless the vote
+		/// was waiting on the authorities to decrypt its ballots or on its
+		/// delegators to be counted.
+		pub fn force_cancel_vote(origin, vote_id: u64) -> Result {
+			T::CancelOrigin::ensure_origin(origin)?;
+			let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+			let waiting = record.data.stage == VoteStage::Decrypting || record.data.stage == VoteStage::Tallying;
+			Self::do_cancel_vote(record)?;
+			if waiting {
+				Self::return_creation_deposit(vote_id);
+			} else {
+				Self::slash_creation_deposit(vote_id);
+			}
+			Ok(())
+		}
+
+		/// Advance every scheduled vote whose current stage has ended, and stop
+		/// tracking the ones that completed or were cancelled. Then complete the
+		/// votes whose decryption deadline passed, and prune the votes that
+		/// finished more than the retention period ago.
+		fn on_finalise(n: T::BlockNumber) {
+			Self::migrate_vote_records();
+			Self::process_recounts(MAX_RECOUNTS_PER_BLOCK);
+			let now = <timestamp::Module<T>>::get();
+			let active: Vec<u64> = <ScheduledVotes<T>>::get()
+				.into_iter()
+				.filter(|id| {
+					// a block may end both the commit and the voting stage
+					while let (Some(record), Some(schedule)) = (<VoteRecords<T>>::get(*id), Self::vote_schedule(*id)) {
+						if !Self::stage_ended(&schedule, record.data.stage, n, now) || Self::advance_stage(*id).is_err() {
+							break;
+						}
+					}
+					Self::vote_records(*id).map_or(false, |r| Self::is_open(r.data.stage))
+				})
+				.collect();
+			<ScheduledVotes<T>>::put(active);
+			// votes the authorities did not decrypt in time complete without the
+			// ballots still encrypted
+			Self::decrypting_votes().into_iter()
+				.filter(|id| Self::decryption_deadline(*id).map_or(false, |end| n >= end))
+				.for_each(|id| { let _ = Self::advance_stage(id); });
+			Self::prune_finished_votes(n);
+		}
 	}
 }
 
 impl<T: Trait> Module<T> {
-	pub fn create_vote(
+	pub fn do_create_vote(
 		sender: T::AccountId,
 		vote_type: VoteType,
 		is_commit_reveal: bool,
 		tally_type: TallyType,
-		outcomes: Vec<VoteOutcome>
+		outcomes: Vec<VoteOutcome>,
+		schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>,
+		rules: VoteRules<T::Balance>
+	) -> result::Result<u64, &'static str> {
+		match vote_type {
+			VoteType::Binary => ensure!(outcomes.len() == 2, "Invalid binary outcomes"),
+			VoteType::MultiOption => ensure!(outcomes.len() > 2, "Invalid multi option outcomes"),
+			VoteType::Approval => ensure!(outcomes.len() >= 2, "Invalid approval outcomes"),
+			VoteType::Committee(seats) => {
+				ensure!(seats > 0 && (seats as usize) < outcomes.len(), "Committee must have fewer seats than outcomes");
+				ensure!(tally_type == TallyType::OneCoin, "Committee elections require a coin-weighted tally");
+			},
+			VoteType::Scalar(min, max, step) => {
+				ensure!(outcomes.is_empty(), "Scalar votes take a range instead of outcomes");
+				ensure!(min < max && step > 0, "Invalid scalar range");
+			},
+			VoteType::AnonymousRing => return Err("Anonymous ring votes must be created with a ring"),
+			VoteType::MerkleMembership => return Err("Merkle votes must be created with a merkle root"),
+		}
+
+		Self::new_vote(VoteData {
+			initiator: sender,
+			stage: VoteStage::PreVoting,
+			vote_type: vote_type,
+			tally_type: tally_type,
+			is_commit_reveal: is_commit_reveal,
+			merkle_root: None,
+			rules: rules,
+		}, outcomes, schedule)
+	}
+
+	fn do_reveal(
+		sender: T::AccountId,
+		vote_id: u64,
+		vote: Ballot,
+		secret: Option<VoteOutcome>,
+		conviction: u8
+	) -> Result {
+		let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+		ensure!(record.data.stage == VoteStage::Voting, "Vote is not in voting stage");
+		ensure!(!<VoteKeys<T>>::exists(vote_id), "Encrypted votes must be cast with an encrypted ballot");
+		ensure!(Self::is_eligible(&record, &sender), "Sender is not eligible to vote");
+		Self::check_ballot(&record, &vote)?;
+		if record.data.tally_type == TallyType::Conviction {
+			ensure!(conviction <= Self::max_lock_periods(), "Conviction exceeds the maximum lock periods");
+		} else {
+			ensure!(conviction == 0, "Conviction is only available for conviction votes");
+		}
+
+		// Ensure voter committed
+		if record.data.is_commit_reveal {
+			let commit = Self::commitment_of((record.id, sender.clone())).ok_or("Sender has not committed")?;
+			let secret = secret.ok_or("Commit-reveal votes must be revealed with a secret")?;
+
+			let buf = commitment_preimage(&sender, &secret, &vote);
+			let hash = T::Hashing::hash_of(&buf);
+			ensure!(hash.encode() == commit.encode(), "Commitments do not match");
+			Self::return_bond(record.id, &sender);
+		}
+
+		let id = record.id;
+		if record.data.tally_type == TallyType::Conviction {
+			Self::set_conviction(id, &sender, conviction);
+		}
+		// Replace an earlier reveal by the sender while voting is open
+		let old = Self::insert_reveal(id, &sender, vote.clone());
+		Self::recount(&record, &sender);
+		Self::queue_recount(Recount::Delegators(id, sender.clone(), 0));
+		Self::process_recounts(MAX_RECOUNTS_PER_CALL);
+		match old {
+			Some(old) => Self::deposit_event(RawEvent::VoteChanged(id, sender, old, vote)),
+			None => Self::deposit_event(RawEvent::VoteRevealed(id, sender, vote)),
+		}
+		Ok(())
+	}
+
+	// checks an anonymous ring reveal against the vote and its ring
+	fn check_ring_reveal(vote_id: u64, vote: &Ballot, signature: &RingSignature) -> Result {
+		let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+		ensure!(record.data.vote_type == VoteType::AnonymousRing, "Vote is not an anonymous ring vote");
+		ensure!(record.data.stage == VoteStage::Voting, "Vote is not in voting stage");
+		ensure!(vote.len() == 1, "Vote must select a single outcome");
+		ensure!(record.outcomes.iter().any(|o| o == &vote[0]), "Invalid vote outcome");
+		ensure!(!<KeyImages<T>>::exists((vote_id, signature.key_image)), "Duplicate votes are not allowed");
+
+		let message = (vote_id, vote).encode();
+		let ring = Self::vote_ring(vote_id);
+		ensure!(ring::verify(&ring, &vote_id.encode(), &message, signature), "Invalid ring signature");
+		Ok(())
+	}
+
+	// checks a merkle reveal against the vote and its commitment tree
+	fn check_merkle_reveal(
+		vote_id: u64,
+		vote: &Ballot,
+		nullifier: &[u8; 32],
+		proof: &MerkleProof,
+		signature: &RingSignature
+	) -> Result {
+		let record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
+		ensure!(record.data.vote_type == VoteType::MerkleMembership, "Vote is not a merkle vote");
+		ensure!(record.data.stage == VoteStage::Voting, "Vote is not in voting stage");
+		ensure!(vote.len() == 1, "Vote must select a single outcome");
+		ensure!(record.outcomes.iter().any(|o| o == &vote[0]), "Invalid vote outcome");
+		ensure!(!<Nullifiers<T>>::exists((vote_id, *nullifier)), "Duplicate votes are not allowed");
+
+		let root = record.data.merkle_root.ok_or("Vote has no merkle root")?;
+		let leaf = Self::hash_bytes(nullifier);
+		ensure!(Self::merkle_root_of(leaf, proof) == Some(root), "Invalid membership proof");
+
+		// a ring of one is a plain signature by the nullifier's key
+		let message = (vote_id, vote).encode();
+		ensure!(ring::verify(&[*nullifier], &vote_id.encode(), &message, signature), "Invalid ballot signature");
+		Ok(())
+	}
+
+	// the pool tag of an unsigned reveal, by vote and key image or nullifier
+	fn unsigned_tag(tag: (u64, PublicKey)) -> Vec<u8> {
+		let mut buf = UNSIGNED_REVEAL_TAG.to_vec();
+		buf.extend_from_slice(&tag.encode());
+		buf
+	}
+
+	/// Checks an unsigned call before it enters the transaction pool. Only
+	/// ring and merkle reveals that would be accepted now are valid, and each
+	/// provides its key image or nullifier, so the pool keeps a single reveal
+	/// for each and drops them once the vote leaves its voting stage.
+	pub fn validate_unsigned(call: &Call<T>) -> TransactionValidity {
+		let checked = match *call {
+			Call::reveal_anonymous(vote_id, ref vote, ref signature) =>
+				Self::check_ring_reveal(vote_id, vote, signature).map(|_| (vote_id, signature.key_image)),
+			Call::reveal_merkle(vote_id, ref vote, nullifier, ref proof, ref signature) =>
+				Self::check_merkle_reveal(vote_id, vote, &nullifier, proof, signature).map(|_| (vote_id, nullifier)),
+			_ => return TransactionValidity::Invalid(INVALID_UNSIGNED_CALL),
+		};
+		match checked {
+			Ok(tag) => TransactionValidity::Valid {
+				priority: 0,
+				requires: vec![],
+				provides: vec![Self::unsigned_tag(tag)],
+				longevity: TransactionLongevity::max_value(),
+			},
+			Err(_) => TransactionValidity::Invalid(INVALID_UNSIGNED_CALL),
+		}
+	}
+
+	// ensures a ballot only holds the vote's outcomes, in the form its vote type takes
+	fn check_ballot(record: &VoteRecord<T::AccountId, T::Balance>, vote: &Ballot) -> Result {
+		ensure!(!vote.is_empty(), "Ballot must not be empty");
+		let is_scalar = match record.data.vote_type {
+			VoteType::Scalar(..) => true,
+			_ => false,
+		};
+		ensure!(is_scalar || vote.iter().all(|v| record.outcomes.iter().any(|o| o == v)), "Invalid vote outcome");
+		match record.data.vote_type {
+			VoteType::AnonymousRing => return Err("Anonymous ring votes must be revealed with a ring signature"),
+			VoteType::MerkleMembership => return Err("Merkle votes must be revealed with a membership proof"),
+			VoteType::MultiOption => ensure!(
+				vote.iter().enumerate().all(|(i, v)| !vote[..i].contains(v)),
+				"Ranked ballot contains duplicate outcomes"
+			),
+			VoteType::Approval | VoteType::Committee(_) => ensure!(
+				vote.iter().enumerate().all(|(i, v)| !vote[..i].contains(v)),
+				"Approval ballot contains duplicate outcomes"
+			),
+			VoteType::Scalar(min, max, step) => ensure!(
+				vote.len() == 1 && scalar_value(&vote[0])
+					.map_or(false, |v| v >= min && v <= max && (v - min) % step == 0),
+				"Vote must select a single value in the scalar range"
+			),
+			_ => ensure!(vote.len() == 1, "Vote must select a single outcome"),
+		}
+		Ok(())
+	}
+
+	// combines the shares of an encrypted ballot and reveals it for its
+	// voter, or discards it if it does not decrypt to a valid ballot
+	fn decrypt_ballot(
+		record: &VoteRecord<T::AccountId, T::Balance>,
+		key: &ThresholdKey,
+		voter: &T::AccountId,
+		ciphertext: &Ciphertext,
+		shares: &[DecryptionShare]
+	) {
+		let context = (record.id, voter).encode();
+		let ballot = threshold::decrypt(key, ciphertext, shares, &context)
+			.and_then(|plaintext| Ballot::decode(&mut &plaintext[..]))
+			.filter(|ballot| Self::check_ballot(record, ballot).is_ok());
+
+		<Ciphertexts<T>>::remove((record.id, voter.clone()));
+		<DecryptionShares<T>>::remove((record.id, voter.clone()));
+		<PendingDecryptions<T>>::mutate(record.id, |n| *n = n.saturating_sub(1));
+		match ballot {
+			Some(ballot) => {
+				Self::insert_reveal(record.id, voter, ballot.clone());
+				Self::recount(record, voter);
+				Self::queue_recount(Recount::Delegators(record.id, voter.clone(), 0));
+				Self::deposit_event(RawEvent::VoteRevealed(record.id, voter.clone(), ballot));
+			},
+			None => Self::deposit_event(RawEvent::EncryptedBallotDiscarded(record.id, voter.clone())),
+		}
+	}
+
+	// checks a relayed ballot's nonce and signature, then reveals it for the
+	// signer. The nonce is used up once the signature checks out.
+	fn reveal_signed(ballot: SignedBallot<T::AccountId, T::Signature>) -> Result {
+		ensure!(ballot.nonce == Self::ballot_nonce(&ballot.signer), "Invalid ballot nonce");
+		let genesis_hash = <system::Module<T>>::block_hash(T::BlockNumber::zero());
+		let payload = signed_ballot_payload(&genesis_hash, ballot.vote_id, &ballot.ballot, ballot.nonce);
+		ensure!(ballot.signature.verify(&payload[..], &ballot.signer), "Invalid ballot signature");
+		<BallotNonces<T>>::insert(&ballot.signer, ballot.nonce + 1);
+		Self::do_reveal(ballot.signer, ballot.vote_id, ballot.ballot, None, 0)
+	}
+
+	/// Creates a vote whose ballots are encrypted to the authorities' current
+	/// threshold key. Once voting ends the vote waits in the decrypting stage
+	/// until the authorities have decrypted every ballot, then completes. If
+	/// the decryption period passes first, it completes with the ballots
+	/// decrypted so far and leaves the rest out.
+	pub fn create_encrypted_vote(
+		sender: T::AccountId,
+		vote_type: VoteType,
+		tally_type: TallyType,
+		outcomes: Vec<VoteOutcome>,
+		schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>,
+		rules: VoteRules<T::Balance>
+	) -> result::Result<u64, &'static str> {
+		let key = Self::authority_key().ok_or("No threshold key has been set")?;
+		ensure!(tally_type != TallyType::Conviction, "Encrypted votes cannot use conviction");
+		let id = Self::do_create_vote(sender, vote_type, false, tally_type, outcomes, schedule, rules)?;
+		<VoteKeys<T>>::insert(id, key);
+		Ok(id)
+	}
+
+	/// Creates an anonymous vote whose ballots must be ring-signed by one of
+	/// the keys in `ring`. Every key may vote once and counts equally.
+	pub fn create_ring_vote(
+		sender: T::AccountId,
+		outcomes: Vec<VoteOutcome>,
+		ring: Vec<PublicKey>,
+		schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>,
+		rules: VoteRules<T::Balance>
 	) -> result::Result<u64, &'static str> {
-		// TODO: Origin check? sender?
-		ensure!(vote_type == VoteType::Binary || vote_type == VoteType::MultiOption, "Unsupported vote type");
+		ensure!(outcomes.len() >= 2, "Invalid anonymous ring outcomes");
+		ensure!(ring.len() >= 2, "Ring must contain at least two keys");
+		ensure!(ring.iter().all(|k| ring::is_valid_key(k)), "Ring contains an invalid key");
+		ensure!(ring.iter().enumerate().all(|(i, k)| !ring[..i].contains(k)), "Ring contains duplicate keys");
 
-		if vote_type == VoteType::Binary { ensure!(outcomes.len() == 2, "Invalid binary outcomes") }
-		if vote_type  == VoteType::MultiOption { ensure!(outcomes.len() > 2, "Invalid multi option outcomes") }
+		let id = Self::new_vote(VoteData {
+			initiator: sender,
+			stage: VoteStage::PreVoting,
+			vote_type: VoteType::AnonymousRing,
+			tally_type: TallyType::OnePerson,
+			is_commit_reveal: false,
+			merkle_root: None,
+			rules: rules,
+		}, outcomes, schedule)?;
+		<VoteRings<T>>::insert(id, ring);
+		Ok(id)
+	}
+
+	/// Creates a vote open to the holders of the commitments in the merkle tree
+	/// with root `merkle_root`. A voter commits to `hash(nullifier)` for a
+	/// one-time public key as nullifier, and reveals the nullifier, which may
+	/// only be used once per vote. This is not anonymous: the reveal exposes
+	/// the voter's leaf, so whoever built the tree or knows which voter holds
+	/// each leaf can link every ballot to its voter. Voters only stay unlinked
+	/// from their accounts towards everyone else.
+	pub fn create_merkle_vote(
+		sender: T::AccountId,
+		outcomes: Vec<VoteOutcome>,
+		merkle_root: [u8; 32],
+		schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>,
+		rules: VoteRules<T::Balance>
+	) -> result::Result<u64, &'static str> {
+		ensure!(outcomes.len() >= 2, "Invalid merkle outcomes");
+		Self::new_vote(VoteData {
+			initiator: sender,
+			stage: VoteStage::PreVoting,
+			vote_type: VoteType::MerkleMembership,
+			tally_type: TallyType::OnePerson,
+			is_commit_reveal: false,
+			merkle_root: Some(merkle_root),
+			rules: rules,
+		}, outcomes, schedule)
+	}
+
+	fn new_vote(
+		data: VoteData<T::AccountId, T::Balance>,
+		outcomes: Vec<VoteOutcome>,
+		schedule: Option<VoteSchedule<T::BlockNumber, T::Moment>>
+	) -> result::Result<u64, &'static str> {
+		ensure!(outcomes.iter().enumerate().all(|(i, o)| !outcomes[..i].contains(o)), "Vote outcomes must be unique");
+		Self::migrate_vote_records();
+		if let Some(schedule) = schedule {
+			Self::check_schedule(&schedule, data.is_commit_reveal)?;
+		}
+		Self::check_rules(&data)?;
 
 		let id = Self::vote_record_count() + 1;
+		let sender = data.initiator.clone();
+		let vote_type = data.vote_type;
 		<VoteRecords<T>>::insert(id, VoteRecord {
 			id: id,
-			commitments: vec![],
-			reveals: vec![],
 			outcomes: outcomes,
-			data: VoteData {
-				initiator: sender.clone(),
-				stage: VoteStage::PreVoting,
-				vote_type: vote_type,
-				tally_type: tally_type,
-				is_commit_reveal: is_commit_reveal,
-			},
+			data: data,
 		});
 
 		<VoteRecordCount<T>>::mutate(|i| *i += 1);
+		Self::record_stage_time(id, VoteStage::PreVoting);
 		Self::deposit_event(RawEvent::VoteCreated(id, sender, vote_type));
+
+		// scheduled votes open immediately and are advanced in `on_finalise`
+		if let Some(schedule) = schedule {
+			<VoteSchedules<T>>::insert(id, schedule);
+			<ScheduledVotes<T>>::mutate(|votes| votes.push(id));
+			Self::advance_stage(id)?;
+		}
 		return Ok(id);
 	}
 
+	// ensures the schedule has a commit stage iff the vote is commit-reveal,
+	// and that every stage ends in the future and after the one before it
+	fn check_schedule(schedule: &VoteSchedule<T::BlockNumber, T::Moment>, is_commit_reveal: bool) -> Result {
+		match schedule {
+			VoteSchedule::Blocks(commit_end, voting_end) => {
+				ensure!(commit_end.is_some() == is_commit_reveal, "Schedule must end a commit stage iff the vote is commit-reveal");
+				let first_end = commit_end.unwrap_or(*voting_end);
+				ensure!(first_end > <system::Module<T>>::block_number(), "Scheduled stages must end in the future");
+				ensure!(first_end <= *voting_end, "Commit stage must end before voting stage");
+			},
+			VoteSchedule::Moments(commit_end, voting_end) => {
+				ensure!(commit_end.is_some() == is_commit_reveal, "Schedule must end a commit stage iff the vote is commit-reveal");
+				let first_end = commit_end.unwrap_or(*voting_end);
+				ensure!(first_end > <timestamp::Module<T>>::get(), "Scheduled stages must end in the future");
+				ensure!(first_end <= *voting_end, "Commit stage must end before voting stage");
+			},
+		}
+		Ok(())
+	}
+
+	// ensures percentages are in range, that issuance quorums only apply to
+	// coin-weighted votes and that ring and merkle votes, whose ballots name
+	// no account, keep their own voter sets
+	fn check_rules(data: &VoteData<T::AccountId, T::Balance>) -> Result {
+		let rules = &data.rules;
+		let tally_type = data.tally_type;
+		let is_unsigned = data.vote_type == VoteType::AnonymousRing || data.vote_type == VoteType::MerkleMembership;
+		ensure!(
+			!is_unsigned || rules.eligibility == VoterPolicy::Anyone,
+			"Ring and merkle votes cannot restrict eligibility"
+		);
+		match rules.quorum {
+			Some(Quorum::PercentOfIssuance(percent)) => {
+				ensure!(percent <= 100, "Quorum must be at most 100 percent");
+				ensure!(tally_type == TallyType::OneCoin, "Issuance quorum requires a coin-weighted tally");
+			},
+			Some(Quorum::PercentOfAccounts(percent, eligible)) => {
+				ensure!(percent <= 100, "Quorum must be at most 100 percent");
+				ensure!(eligible > 0, "Quorum must have eligible accounts");
+			},
+			None => {},
+		}
+		if let Threshold::Supermajority(percent) = rules.threshold {
+			ensure!(percent > 50 && percent <= 100, "Supermajority must be between 51 and 100 percent");
+		}
+		Ok(())
+	}
+
+	// whether the given stage of a scheduled vote is over at block `n`, time `now`
+	fn stage_ended(
+		schedule: &VoteSchedule<T::BlockNumber, T::Moment>,
+		stage: VoteStage,
+		n: T::BlockNumber,
+		now: T::Moment
+	) -> bool {
+		match (schedule, stage) {
+			(VoteSchedule::Blocks(Some(end), _), VoteStage::Commit) => n >= *end,
+			(VoteSchedule::Blocks(_, end), VoteStage::Voting) => n >= *end,
+			(VoteSchedule::Moments(Some(end), _), VoteStage::Commit) => now >= *end,
+			(VoteSchedule::Moments(_, end), VoteStage::Voting) => now >= *end,
+			// decrypting and tallying votes complete once their ballots and
+			// delegators are counted
+			(_, VoteStage::Decrypting) | (_, VoteStage::Tallying) => true,
+			_ => false,
+		}
+	}
+
+	// starts the decryption deadline of a vote whose voting ended with
+	// encrypted ballots
+	fn start_decryption(vote_id: u64) {
+		let period = Self::decryption_period();
+		if !period.is_zero() {
+			let now = <system::Module<T>>::block_number();
+			<DecryptionDeadlines<T>>::insert(vote_id, now + period);
+		}
+		<DecryptingVotes<T>>::mutate(|votes| votes.push(vote_id));
+	}
+
+	// whether a decrypting vote has passed its decryption deadline
+	fn decryption_expired(vote_id: u64) -> bool {
+		Self::decryption_deadline(vote_id)
+			.map_or(false, |end| <system::Module<T>>::block_number() >= end)
+	}
+
+	// stops tracking a vote that left the decrypting stage. Ballots still
+	// encrypted are left out of the tally and removed when the vote is pruned.
+	fn end_decryption(vote_id: u64) {
+		let undecrypted = Self::pending_decryptions(vote_id);
+		if undecrypted > 0 {
+			<PendingDecryptions<T>>::insert(vote_id, 0);
+			Self::deposit_event(RawEvent::DecryptionExpired(vote_id, undecrypted));
+		}
+		<DecryptionDeadlines<T>>::remove(vote_id);
+		<DecryptingVotes<T>>::mutate(|votes| votes.retain(|id| *id != vote_id));
+	}
+
 	pub fn advance_stage(vote_id: u64) -> Result {
 		let mut record = <VoteRecords<T>>::get(vote_id).ok_or("Vote record does not exist")?;
 		let curr_stage = record.data.stage;
 		let next_stage = match curr_stage {
 			VoteStage::PreVoting if record.data.is_commit_reveal => VoteStage::Commit,
 			VoteStage::PreVoting | VoteStage::Commit => VoteStage::Voting,
+			VoteStage::Voting if Self::pending_decryptions(vote_id) > 0 => VoteStage::Decrypting,
+			VoteStage::Voting if Self::pending_recounts(vote_id) > 0 => VoteStage::Tallying,
 			VoteStage::Voting => VoteStage::Completed,
+			VoteStage::Tallying => {
+				ensure!(Self::pending_recounts(vote_id) == 0, "Delegators are still being counted");
+				VoteStage::Completed
+			},
+			VoteStage::Decrypting => {
+				ensure!(
+					Self::pending_decryptions(vote_id) == 0 || Self::decryption_expired(vote_id),
+					"Encrypted ballots are still being decrypted"
+				);
+				ensure!(Self::pending_recounts(vote_id) == 0, "Delegators are still being counted");
+				VoteStage::Completed
+			},
 			VoteStage::Completed => return Err("Vote already completed"),
+			VoteStage::Cancelled => return Err("Vote was cancelled"),
 		};
 		record.data.stage = next_stage;
+		if next_stage == VoteStage::Decrypting {
+			Self::start_decryption(vote_id);
+		} else if curr_stage == VoteStage::Decrypting {
+			Self::end_decryption(vote_id);
+		}
+		let vote_type = record.data.vote_type;
+		// delegation changes only need to reach votes that are open, and
+		// nothing new is queued for a vote once its voting ends
+		let is_unsigned = vote_type == VoteType::AnonymousRing || vote_type == VoteType::MerkleMembership;
+		if next_stage == VoteStage::Voting && !is_unsigned {
+			<ActiveVotes<T>>::mutate(|votes| votes.push(vote_id));
+		} else if curr_stage == VoteStage::Voting {
+			<ActiveVotes<T>>::mutate(|votes| votes.retain(|id| *id != vote_id));
+		}
+		// voters stay locked until their lock periods are started
+		if next_stage == VoteStage::Completed && record.data.tally_type == TallyType::Conviction {
+			Self::queue_recount(Recount::Locks(vote_id, 0));
+		}
+		if next_stage == VoteStage::Completed && record.data.is_commit_reveal {
+			Self::slash_unrevealed_bonds(vote_id);
+		}
+		if next_stage == VoteStage::Completed {
+			Self::return_creation_deposit(vote_id);
+			Self::mark_finished(vote_id);
+		}
+		Self::record_stage_time(vote_id, next_stage);
 		<VoteRecords<T>>::insert(record.id, record);
+		// keep the elimination rounds of finished runoffs for clients
+		if next_stage == VoteStage::Completed && vote_type == VoteType::MultiOption {
+			if let Some(rounds) = Self::compute_runoff_rounds(vote_id) {
+				<RunoffRounds<T>>::insert(vote_id, rounds);
+			}
+		}
 		Self::deposit_event(RawEvent::VoteAdvanced(vote_id, curr_stage, next_stage));
+		if next_stage == VoteStage::Completed {
+			if let Some(result) = Self::result(vote_id) {
+				T::OnVoteCompleted::on_vote_completed(vote_id, result);
+			}
+		}
+		Ok(())
+	}
+
+	fn do_cancel_vote(mut record: VoteRecord<T::AccountId, T::Balance>) -> Result {
+		ensure!(Self::is_open(record.data.stage), "Vote is already finished");
+		let id = record.id;
+		let stage = record.data.stage;
+		record.data.stage = VoteStage::Cancelled;
+		if stage == VoteStage::Decrypting {
+			<DecryptionDeadlines<T>>::remove(id);
+			<DecryptingVotes<T>>::mutate(|votes| votes.retain(|v| *v != id));
+		}
+		// nobody could have failed to reveal on a withdrawn vote
+		if record.data.is_commit_reveal {
+			Self::committers_of(id).iter().for_each(|who| Self::return_bond(id, who));
+		}
+		<VoteRecords<T>>::insert(id, record);
+		<ActiveVotes<T>>::mutate(|votes| votes.retain(|v| *v != id));
+		<ScheduledVotes<T>>::mutate(|votes| votes.retain(|v| *v != id));
+		Self::record_stage_time(id, VoteStage::Cancelled);
+		Self::mark_finished(id);
+		Self::deposit_event(RawEvent::VoteCancelled(id, stage));
+		// modules advancing their own votes must stop tracking this one
+		T::OnVoteCompleted::on_vote_cancelled(id);
 		Ok(())
 	}
 
+	// releases the commitment bond an account reserved on a vote, if any
+	fn return_bond(vote_id: u64, who: &T::AccountId) {
+		if let Some(bond) = Self::commit_bond_of((vote_id, who.clone())) {
+			<balances::Module<T>>::unreserve(who, bond);
+			<CommitBonds<T>>::remove((vote_id, who.clone()));
+			Self::deposit_event(RawEvent::CommitBondReturned(vote_id, who.clone(), bond));
+		}
+	}
+
+	// releases the deposit reserved by the initiator of a vote, if any
+	fn return_creation_deposit(vote_id: u64) {
+		if let Some((who, deposit)) = Self::creation_deposit_of(vote_id) {
+			<balances::Module<T>>::unreserve(&who, deposit);
+			<CreationDeposits<T>>::remove(vote_id);
+			Self::deposit_event(RawEvent::CreationDepositReturned(vote_id, who, deposit));
+		}
+	}
+
+	// slashes the deposit reserved by the initiator of a vote, if any
+	fn slash_creation_deposit(vote_id: u64) {
+		if let Some((who, deposit)) = Self::creation_deposit_of(vote_id) {
+			let remaining = <balances::Module<T>>::slash_reserved(&who, deposit).unwrap_or(Zero::zero());
+			let slashed = deposit.saturating_sub(remaining);
+			<CreationDeposits<T>>::remove(vote_id);
+			T::OnBondSlashed::on_bond_slashed(slashed);
+			Self::deposit_event(RawEvent::CreationDepositSlashed(vote_id, who, slashed));
+		}
+	}
+
+	// slashes the bonds still held on a completed vote, which belong to the
+	// accounts that committed but never revealed
+	fn slash_unrevealed_bonds(vote_id: u64) {
+		for who in Self::committers_of(vote_id) {
+			if let Some(bond) = Self::commit_bond_of((vote_id, who.clone())) {
+				// the reserved balance may already have been slashed elsewhere
+				let remaining = <balances::Module<T>>::slash_reserved(&who, bond).unwrap_or(Zero::zero());
+				let slashed = bond.saturating_sub(remaining);
+				<CommitBonds<T>>::remove((vote_id, who.clone()));
+				T::OnBondSlashed::on_bond_slashed(slashed);
+				Self::deposit_event(RawEvent::CommitBondSlashed(vote_id, who, slashed));
+			}
+		}
+	}
+
+	// notes the time a vote entered a stage
+	fn record_stage_time(vote_id: u64, stage: VoteStage) {
+		let now = <timestamp::Module<T>>::get();
+		<StageTimes<T>>::mutate(vote_id, |times| times.push((stage, now)));
+	}
+
+	// queues a vote that completed or was cancelled for pruning
+	fn mark_finished(vote_id: u64) {
+		let now = <system::Module<T>>::block_number();
+		<FinishedVotes<T>>::mutate(|votes| votes.push((vote_id, now)));
+	}
+
+	// prunes the votes whose retention period is over, oldest first. Each block
+	// removes a bounded number of storage entries, and a vote too large to
+	// prune in one block is resumed in the next. A zero retention period keeps
+	// finished votes forever.
+	fn prune_finished_votes(n: T::BlockNumber) {
+		let mut budget = MAX_PRUNED_REMOVALS_PER_BLOCK;
+		while budget > 0 {
+			let mut cursor = match Self::pruning().or_else(|| Self::next_expired_vote(n)) {
+				Some(cursor) => cursor,
+				None => break,
+			};
+			if Self::step_prune(&mut cursor, &mut budget) {
+				<Pruning<T>>::kill();
+			} else {
+				<Pruning<T>>::put(cursor);
+			}
+		}
+	}
+
+	// takes the oldest finished vote off the queue once its retention period is over
+	fn next_expired_vote(n: T::BlockNumber) -> Option<PruneCursor<T::Hash>> {
+		let retention = Self::retention_period();
+		if retention.is_zero() {
+			return None;
+		}
+
+		let mut finished = Self::finished_votes();
+		match finished.first() {
+			Some((_, at)) if at.saturating_add(retention) <= n => (),
+			_ => return None,
+		}
+		let (vote_id, _) = finished.remove(0);
+		<FinishedVotes<T>>::put(finished);
+		Some(PruneCursor {
+			vote_id: vote_id,
+			step: PruneStep::Voters(0),
+			ballots_hash: Default::default(),
+		})
+	}
+
+	// removes as much of a finished vote's storage as the budget allows, then
+	// compacts the vote into its final tally, the hash of its ballots and its
+	// stage times. Returns whether the vote is fully pruned.
+	fn step_prune(cursor: &mut PruneCursor<T::Hash>, budget: &mut usize) -> bool {
+		let vote_id = cursor.vote_id;
+		if !<VoteRecords<T>>::exists(vote_id) {
+			*budget = budget.saturating_sub(1);
+			return true;
+		}
+
+		while *budget > 0 {
+			cursor.step = match cursor.step {
+				PruneStep::Voters(index) if index < Self::reveal_count(vote_id) => {
+					if let Some(who) = Self::voter((vote_id, index)) {
+						if let Some(ballot) = Self::reveal_of((vote_id, who.clone())) {
+							cursor.ballots_hash = T::Hashing::hash_of(&(&cursor.ballots_hash, &who, &ballot));
+						}
+						<Reveals<T>>::remove((vote_id, who.clone()));
+						<Convictions<T>>::remove((vote_id, who));
+					}
+					<Voters<T>>::remove((vote_id, index));
+					*budget = budget.saturating_sub(3);
+					PruneStep::Voters(index + 1)
+				},
+				PruneStep::Voters(_) => PruneStep::Anonymous(0),
+				PruneStep::Anonymous(index) => {
+					let anonymous = Self::anonymous_reveals(vote_id);
+					let mut index = index as usize;
+					while *budget > 0 && index < anonymous.len() {
+						let (tag, ref ballot) = anonymous[index];
+						cursor.ballots_hash = T::Hashing::hash_of(&(&cursor.ballots_hash, &tag, ballot));
+						<KeyImages<T>>::remove((vote_id, tag));
+						<Nullifiers<T>>::remove((vote_id, tag));
+						*budget = budget.saturating_sub(2);
+						index += 1;
+					}
+					if index < anonymous.len() {
+						PruneStep::Anonymous(index as u64)
+					} else {
+						PruneStep::Committers(0)
+					}
+				},
+				PruneStep::Committers(index) if index < Self::commitment_count(vote_id) => {
+					if let Some(who) = Self::committer((vote_id, index)) {
+						<Commitments<T>>::remove((vote_id, who));
+					}
+					<Committers<T>>::remove((vote_id, index));
+					*budget = budget.saturating_sub(2);
+					PruneStep::Committers(index + 1)
+				},
+				PruneStep::Committers(_) => PruneStep::Contributors(0),
+				PruneStep::Contributors(index) if index < Self::contributor_count(vote_id) => {
+					if let Some(who) = Self::contributor((vote_id, index)) {
+						<Contributions<T>>::remove((vote_id, who.clone()));
+						<PendingLocks<T>>::mutate(&who, |votes| votes.retain(|id| *id != vote_id));
+					}
+					<Contributors<T>>::remove((vote_id, index));
+					*budget = budget.saturating_sub(3);
+					PruneStep::Contributors(index + 1)
+				},
+				PruneStep::Contributors(_) => PruneStep::Allowlist(0),
+				PruneStep::Allowlist(index) => {
+					let allowlisted = Self::allowlisted(vote_id);
+					let mut index = index as usize;
+					while *budget > 0 && index < allowlisted.len() {
+						<Allowlist<T>>::remove((vote_id, allowlisted[index].clone()));
+						*budget = budget.saturating_sub(1);
+						index += 1;
+					}
+					if index < allowlisted.len() {
+						PruneStep::Allowlist(index as u64)
+					} else {
+						PruneStep::Encrypted(0)
+					}
+				},
+				// ballots left encrypted on a vote cancelled while decrypting
+				PruneStep::Encrypted(index) if index < Self::ciphertext_count(vote_id) => {
+					if let Some(who) = Self::encrypted_voter((vote_id, index)) {
+						<Ciphertexts<T>>::remove((vote_id, who.clone()));
+						<DecryptionShares<T>>::remove((vote_id, who));
+					}
+					<EncryptedVoters<T>>::remove((vote_id, index));
+					*budget = budget.saturating_sub(3);
+					PruneStep::Encrypted(index + 1)
+				},
+				PruneStep::Encrypted(_) => PruneStep::Record,
+				PruneStep::Record => {
+					Self::prune_record(vote_id, cursor.ballots_hash);
+					*budget = budget.saturating_sub(RECORD_REMOVALS);
+					return true;
+				},
+			};
+		}
+		false
+	}
+
+	// stores the compacted form of a vote whose per voter entries are gone and
+	// removes its remaining entries and record
+	fn prune_record(vote_id: u64, ballots_hash: T::Hash) {
+		<PrunedVotes<T>>::insert(vote_id, PrunedVote {
+			tally: Self::tally(vote_id),
+			ballots_hash: ballots_hash,
+			stage_times: Self::stage_times(vote_id),
+		});
+
+		<RevealCount<T>>::remove(vote_id);
+		<CommitmentCount<T>>::remove(vote_id);
+		<ContributorCount<T>>::remove(vote_id);
+		<Allowlisted<T>>::remove(vote_id);
+		<AnonymousReveals<T>>::remove(vote_id);
+		<CiphertextCount<T>>::remove(vote_id);
+		<PendingDecryptions<T>>::remove(vote_id);
+		<DecryptionDeadlines<T>>::remove(vote_id);
+		<VoteKeys<T>>::remove(vote_id);
+		<BallotTotals<T>>::remove(vote_id);
+		<Turnout<T>>::remove(vote_id);
+		<PendingRecounts<T>>::remove(vote_id);
+		<VoteSchedules<T>>::remove(vote_id);
+		<VoteRings<T>>::remove(vote_id);
+		<RunoffRounds<T>>::remove(vote_id);
+		<StageTimes<T>>::remove(vote_id);
+		<VoteRecords<T>>::remove(vote_id);
+		Self::deposit_event(RawEvent::VotePruned(vote_id));
+	}
+
+	/// Whether an account may vote on a vote, and be counted on it through
+	/// delegation, under the vote's eligibility policy.
+	pub fn is_eligible(record: &VoteRecord<T::AccountId, T::Balance>, who: &T::AccountId) -> bool {
+		match record.data.rules.eligibility {
+			VoterPolicy::Anyone => true,
+			VoterPolicy::Allowlist => Self::is_allowed((record.id, who.clone())),
+			VoterPolicy::MinBalance(min) => <balances::Module<T>>::free_balance(who) >= min,
+			VoterPolicy::External => T::Eligibility::can_vote(record.id, who),
+		}
+	}
+
+	// whether a vote in this stage may still be voted on or advanced
+	fn is_open(stage: VoteStage) -> bool {
+		stage != VoteStage::Completed && stage != VoteStage::Cancelled
+	}
+
+	// records the lock periods a voter chose, keeping their balance locked
+	// for as long as the vote runs
+	fn set_conviction(vote_id: u64, voter: &T::AccountId, conviction: u8) {
+		<PendingLocks<T>>::mutate(voter, |votes| votes.retain(|id| *id != vote_id));
+		if conviction > 0 {
+			<Convictions<T>>::insert((vote_id, voter.clone()), conviction);
+			<PendingLocks<T>>::mutate(voter, |votes| votes.push(vote_id));
+		} else {
+			<Convictions<T>>::remove((vote_id, voter.clone()));
+		}
+	}
+
+	// once a conviction vote completes, a voter's balance stays locked for
+	// the lock periods they chose, counted from when the lock is started
+	fn start_lock_period(vote_id: u64, voter: &T::AccountId) {
+		let now = <system::Module<T>>::block_number();
+		<PendingLocks<T>>::mutate(voter, |votes| votes.retain(|id| *id != vote_id));
+		let conviction = Self::conviction_of((vote_id, voter.clone()));
+		let periods = <T::BlockNumber as As<u64>>::sa(conviction as u64);
+		let until = now.saturating_add(Self::lock_period().saturating_mul(periods));
+		if until > Self::bondage(voter) {
+			<Bondage<T>>::insert(voter, until);
+		}
+	}
+
+	fn hash_bytes(data: &[u8]) -> [u8; 32] {
+		let mut out = [0u8; 32];
+		out.iter_mut()
+			.zip(T::Hashing::hash(data).as_ref().iter())
+			.for_each(|(o, b)| *o = *b);
+		out
+	}
+
+	// folds a merkle path from a leaf up to the root it proves membership in
+	fn merkle_root_of(leaf: [u8; 32], proof: &MerkleProof) -> Option<[u8; 32]> {
+		// the index must address a leaf of a tree of the path's depth
+		if proof.path.len() > 64 || proof.index.checked_shr(proof.path.len() as u32).unwrap_or(0) != 0 {
+			return None;
+		}
+
+		let mut node = leaf;
+		for (level, sibling) in proof.path.iter().enumerate() {
+			let mut buf = Vec::new();
+			if (proof.index >> level) & 1 == 0 {
+				buf.extend_from_slice(&node);
+				buf.extend_from_slice(sibling);
+			} else {
+				buf.extend_from_slice(sibling);
+				buf.extend_from_slice(&node);
+			}
+			node = Self::hash_bytes(&buf);
+		}
+		Some(node)
+	}
+
 	// for a given account, finds the voter representing them, aka their
 	// closest voting ancestor on the delegation graph (incl self)
-	fn find_rep(voters: &Vec<(T::AccountId, VoteOutcome)>, acct: T::AccountId) -> Option<T::AccountId> {
-		if let Some(_) = voters.iter().find(|(voter, _)| voter == &acct) {
+	fn find_rep(vote_id: u64, acct: T::AccountId) -> Option<T::AccountId> {
+		if <Reveals<T>>::exists((vote_id, acct.clone())) {
 			return Some(acct);
 		} else if let Some(parent) = <delegation::Module<T>>::delegate_of(acct) {
-			return Self::find_rep(voters, parent);
+			return Self::find_rep(vote_id, parent);
 		} else {
 			return None;
 		}
 	}
 
 	// constructs a mapping of accounts to their representatives
-	fn build_rep_map(reps: &mut Vec<(T::AccountId, T::AccountId)>, voters: &Vec<(T::AccountId, VoteOutcome)>, acct: T::AccountId) {
+	fn build_rep_map(reps: &mut Vec<(T::AccountId, T::AccountId)>, vote_id: u64, acct: T::AccountId) {
 		// if we haven't seen this account yet, find its voting parent
 		match reps.iter().find(|(voter, _)| voter == &acct) {
 			Some(_) => return,
 			None => {
-				if let Some(voter) = Self::find_rep(voters, acct.clone()) {
+				if let Some(voter) = Self::find_rep(vote_id, acct.clone()) {
 					reps.push((acct.clone(), voter));
 				}
 			}
@@ -259,55 +1652,668 @@ impl<T: Trait> Module<T> {
 		// recurse to children
 		if let Some(ds) = <delegation::Module<T>>::delegates_to(acct) {
 			ds.into_iter().for_each(|d| {
-				Self::build_rep_map(reps, voters, d);
+				Self::build_rep_map(reps, vote_id, d);
 			});
 		};
 	}
-	
-	pub fn tally(vote_id: u64) -> Tally<T::Balance> {
-		let mut voters: Vec<(T::AccountId, VoteOutcome)> = vec![];
+
+	// the weight an account adds to the ballot it is counted with
+	fn weight_of(record: &VoteRecord<T::AccountId, T::Balance>, account: &T::AccountId) -> T::Balance {
+		match record.data.tally_type {
+			TallyType::OnePerson => One::one(),
+			TallyType::OneCoin => <balances::Module<T>>::free_balance(account),
+			// taken per account, so delegating never dilutes the root
+			TallyType::Quadratic => <balances::Module<T>>::free_balance(account).integer_sqrt(),
+			// only voters choose a conviction, so delegators count once
+			TallyType::Conviction => {
+				let conviction = Self::conviction_of((record.id, account.clone()));
+				<balances::Module<T>>::free_balance(account)
+					.saturating_mul(<T::Balance as As<u64>>::sa(1 + conviction as u64))
+			},
+		}
+	}
+
+	fn add_to_totals(vote_id: u64, ballot: &Ballot, weight: T::Balance) {
+		<BallotTotals<T>>::mutate(vote_id, |totals| match totals.iter().position(|(b, _)| b == ballot) {
+			Some(index) => totals[index].1 = totals[index].1.saturating_add(weight),
+			None => totals.push((ballot.clone(), weight)),
+		});
+		<Turnout<T>>::mutate(vote_id, |turnout| {
+			turnout.0 = turnout.0.saturating_add(1);
+			turnout.1 = turnout.1.saturating_add(weight);
+		});
+	}
+
+	fn remove_from_totals(vote_id: u64, ballot: &Ballot, weight: T::Balance) {
+		<BallotTotals<T>>::mutate(vote_id, |totals| {
+			if let Some(index) = totals.iter().position(|(b, _)| b == ballot) {
+				totals[index].1 = totals[index].1.saturating_sub(weight);
+			}
+		});
+		<Turnout<T>>::mutate(vote_id, |turnout| {
+			turnout.0 = turnout.0.saturating_sub(1);
+			turnout.1 = turnout.1.saturating_sub(weight);
+		});
+	}
+
+	// moves an account's weight to the ballot of its current representative,
+	// weighed by its balance now
+	fn recount(record: &VoteRecord<T::AccountId, T::Balance>, account: &T::AccountId) {
+		let was_counted = match Self::contribution_of((record.id, account.clone())) {
+			Some((ballot, weight)) => {
+				Self::remove_from_totals(record.id, &ballot, weight);
+				true
+			},
+			None => false,
+		};
+
+		// ineligible accounts are not counted, even through a delegate
+		let ballot = if Self::is_eligible(record, account) {
+			Self::find_rep(record.id, account.clone())
+				.and_then(|rep| Self::reveal_of((record.id, rep)))
+		} else {
+			None
+		};
+		match ballot {
+			Some(ballot) => {
+				let weight = Self::weight_of(record, account);
+				Self::add_to_totals(record.id, &ballot, weight);
+				// an account counted again after dropping out is listed twice,
+				// which pruning tolerates
+				if !was_counted {
+					let index = Self::contributor_count(record.id);
+					<Contributors<T>>::insert((record.id, index), account.clone());
+					<ContributorCount<T>>::insert(record.id, index + 1);
+					<CountedOn<T>>::mutate(account, |votes| if !votes.contains(&record.id) { votes.push(record.id) });
+				}
+				<Contributions<T>>::insert((record.id, account.clone()), (ballot, weight));
+			},
+			None => {
+				<Contributions<T>>::remove((record.id, account.clone()));
+				<CountedOn<T>>::mutate(account, |votes| votes.retain(|id| *id != record.id));
+			},
+		}
+	}
+
+	// remembers that coins may be leaving an account counted on an open
+	// vote, so its weights are lowered once the move is done
+	fn note_balance_move(who: &T::AccountId) {
+		// entries for finished votes are dropped lazily
+		let open: Vec<u64> = Self::counted_on(who).into_iter()
+			.filter(|id| Self::vote_records(*id).map_or(false, |r| Self::is_open(r.data.stage)))
+			.collect();
+		if open.is_empty() {
+			<CountedOn<T>>::remove(who);
+			return;
+		}
+		<CountedOn<T>>::insert(who, open);
+		if !Self::moved_balances().contains(who) {
+			<MovedBalances<T>>::mutate(|accounts| accounts.push(who.clone()));
+		}
+	}
+
+	// lowers the weight of each account whose coins moved to what its balance
+	// is worth now, so coins moved to another voter are not counted twice.
+	// The work is bounded by the transfers made since the last settlement.
+	fn settle_balance_moves() {
+		for who in <MovedBalances<T>>::take() {
+			for vote_id in Self::counted_on(&who) {
+				let record = match <VoteRecords<T>>::get(vote_id) {
+					Some(record) if Self::is_open(record.data.stage) => record,
+					_ => continue,
+				};
+				if let Some((ballot, counted)) = Self::contribution_of((vote_id, who.clone())) {
+					let weight = Self::weight_of(&record, &who);
+					if weight < counted {
+						Self::remove_from_totals(vote_id, &ballot, counted);
+						Self::add_to_totals(vote_id, &ballot, weight);
+						<Contributions<T>>::insert((vote_id, who.clone()), (ballot, weight));
+						Self::deposit_event(RawEvent::WeightRefreshed(vote_id, who.clone(), weight));
+					}
+				}
+			}
+		}
+	}
+
+	// queues a recount behind those already waiting
+	fn queue_recount(recount: Recount<T::AccountId>) {
+		if let Recount::Delegators(vote_id, _, _) = recount {
+			<PendingRecounts<T>>::mutate(vote_id, |n| *n += 1);
+		}
+		let tail = Self::recount_tail();
+		<Recounts<T>>::insert(tail, recount);
+		<RecountTail<T>>::put(tail + 1);
+	}
+
+	// works through queued recounts in order, looking at most `budget`
+	// queued recounts and accounts. A recount that runs out of budget keeps
+	// its place and resumes where it stopped.
+	fn process_recounts(mut budget: usize) {
+		// weights are counted down before anything else is counted up
+		Self::settle_balance_moves();
+		while budget > 0 {
+			let head = Self::recount_head();
+			if head >= Self::recount_tail() {
+				break;
+			}
+			let (rest, left) = match Self::queued_recount(head) {
+				Some(recount) => Self::step_recount(recount, budget - 1),
+				None => (None, budget - 1),
+			};
+			budget = left;
+			match rest {
+				Some(rest) => <Recounts<T>>::insert(head, rest),
+				None => {
+					<Recounts<T>>::remove(head);
+					<RecountHead<T>>::put(head + 1);
+				},
+			}
+		}
+	}
+
+	// recounts the accounts a queued recount covers until `budget` runs out,
+	// returning what is left of the recount and of the budget
+	fn step_recount(recount: Recount<T::AccountId>, mut budget: usize) -> (Option<Recount<T::AccountId>>, usize) {
+		match recount {
+			Recount::Delegators(vote_id, account, from) => {
+				// cancelled and pruned votes need no more counting
+				let record = match <VoteRecords<T>>::get(vote_id) {
+					Some(record) if record.data.stage != VoteStage::Cancelled => record,
+					_ => return (None, budget),
+				};
+				let delegators = <delegation::Module<T>>::delegates_to(&account).unwrap_or_default();
+				for (index, d) in delegators.into_iter().enumerate().skip(from as usize) {
+					if budget == 0 {
+						return (Some(Recount::Delegators(vote_id, account, index as u32)), 0);
+					}
+					budget -= 1;
+					// skip stale entries for accounts that moved their delegation
+					// elsewhere, and delegators who voted themselves, who represent
+					// their own subtree
+					if <delegation::Module<T>>::delegate_of(&d).as_ref() != Some(&account)
+						|| <Reveals<T>>::exists((vote_id, d.clone())) {
+						continue;
+					}
+					Self::recount(&record, &d);
+					Self::queue_recount(Recount::Delegators(vote_id, d, 0));
+				}
+				<PendingRecounts<T>>::mutate(vote_id, |n| *n = n.saturating_sub(1));
+				(None, budget)
+			},
+			Recount::Delegation(who, after) => {
+				let mut votes = Self::active_votes();
+				votes.sort();
+				for vote_id in votes.into_iter().filter(|id| *id > after) {
+					if budget == 0 {
+						return (Some(Recount::Delegation(who, vote_id - 1)), 0);
+					}
+					budget -= 1;
+					// voters represent themselves whoever they delegate to
+					if let Some(record) = <VoteRecords<T>>::get(vote_id) {
+						if !<Reveals<T>>::exists((vote_id, who.clone())) {
+							Self::recount(&record, &who);
+							Self::queue_recount(Recount::Delegators(vote_id, who.clone(), 0));
+						}
+					}
+				}
+				(None, budget)
+			},
+			Recount::Locks(vote_id, from) => {
+				for index in from..Self::reveal_count(vote_id) {
+					if budget == 0 {
+						return (Some(Recount::Locks(vote_id, index)), 0);
+					}
+					budget -= 1;
+					if let Some(voter) = Self::voter((vote_id, index)) {
+						Self::start_lock_period(vote_id, &voter);
+					}
+				}
+				(None, budget)
+			},
+		}
+	}
+
+	/// Lists every account counted on a vote with the representative whose
+	/// ballot it was counted with, that ballot and the account's weight. The
+	/// weights behind each ballot add up to its running total.
+	pub fn compute_breakdown(vote_id: u64) -> Vec<(T::AccountId, T::AccountId, Ballot, T::Balance)> {
+		let mut accounts: Vec<T::AccountId> = (0..Self::contributor_count(vote_id))
+			.filter_map(|index| Self::contributor((vote_id, index)))
+			.collect();
+		// accounts counted again after dropping out are listed twice
+		accounts.sort();
+		accounts.dedup();
+		accounts.into_iter()
+			.filter_map(|who| {
+				let (ballot, weight) = Self::contribution_of((vote_id, who.clone()))?;
+				let rep = Self::find_rep(vote_id, who.clone())?;
+				Some((who, rep, ballot, weight))
+			})
+			.collect()
+	}
+
+	/// Recomputes a vote's ballot totals by walking the whole delegation graph.
+	/// This is quadratic in the number of participants and only meant for
+	/// auditing the running totals off-chain.
+	pub fn recount_from_graph(vote_id: u64) -> Option<Vec<(Ballot, T::Balance)>> {
+		let record = <VoteRecords<T>>::get(vote_id)?;
 		let mut reps: Vec<(T::AccountId, T::AccountId)> = vec![];
+		Self::voters_of(vote_id).into_iter()
+			.for_each(|voter| Self::build_rep_map(&mut reps, vote_id, voter));
 
-		if let Some(record) = <VoteRecords<T>>::get(vote_id) {
-			// build mapping of voters to their votes
-			record.reveals.clone().into_iter().for_each(|(acct, choice)| {
-				// build a mapping of voters to their votes
-				voters.push((acct, choice));
+		let mut totals: Vec<(Ballot, T::Balance)> = vec![];
+		for (account, rep) in reps.into_iter() {
+			if !Self::is_eligible(&record, &account) {
+				continue;
+			}
+			let weight = Self::weight_of(&record, &account);
+			let ballot = Self::reveal_of((vote_id, rep))?;
+			match totals.iter().position(|(b, _)| b == &ballot) {
+				Some(index) => totals[index].1 = totals[index].1.saturating_add(weight),
+				None => totals.push((ballot.clone(), weight)),
+			}
+		}
+		Some(totals)
+	}
+
+	// stores an account's commitment, returning the one it replaces
+	fn insert_commitment(vote_id: u64, who: &T::AccountId, commit: VoteOutcome) -> Option<VoteOutcome> {
+		let old = Self::commitment_of((vote_id, who.clone()));
+		if old.is_none() {
+			let index = Self::commitment_count(vote_id);
+			<Committers<T>>::insert((vote_id, index), who.clone());
+			<CommitmentCount<T>>::insert(vote_id, index + 1);
+		}
+		<Commitments<T>>::insert((vote_id, who.clone()), commit);
+		old
+	}
+
+	// stores an account's ballot, returning the one it replaces
+	fn insert_reveal(vote_id: u64, who: &T::AccountId, ballot: Ballot) -> Option<Ballot> {
+		let old = Self::reveal_of((vote_id, who.clone()));
+		if old.is_none() {
+			let index = Self::reveal_count(vote_id);
+			<Voters<T>>::insert((vote_id, index), who.clone());
+			<RevealCount<T>>::insert(vote_id, index + 1);
+		}
+		<Reveals<T>>::insert((vote_id, who.clone()), ballot);
+		old
+	}
+
+	/// The accounts that revealed on a vote, in the order they first revealed
+	pub fn voters_of(vote_id: u64) -> Vec<T::AccountId> {
+		(0..Self::reveal_count(vote_id))
+			.filter_map(|index| Self::voter((vote_id, index)))
+			.collect()
+	}
+
+	/// The accounts that committed on a vote, in the order they first committed
+	pub fn committers_of(vote_id: u64) -> Vec<T::AccountId> {
+		(0..Self::commitment_count(vote_id))
+			.filter_map(|index| Self::committer((vote_id, index)))
+			.collect()
+	}
+
+	// moves the commitments and reveals out of records stored before storage
+	// version 1, revealing each outcome as a ballot of one and giving each
+	// vote the default rules. Runs once, before the upgraded runtime first
+	// creates a vote or finalises a block. Records that do not decode are left
+	// as they were and listed, and the version is only raised once none are.
+	fn migrate_vote_records() {
+		if Self::storage_version() >= 1 || !Self::unmigrated_votes().is_empty() {
+			return;
+		}
+
+		let mut unmigrated = vec![];
+		for id in 1..=Self::vote_record_count() {
+			let key = <VoteRecords<T>>::key_for(id);
+			let legacy = match runtime_support::storage::get::<LegacyVoteRecord<T::AccountId>>(&key) {
+				Some(legacy) => legacy,
+				None => {
+					// ids of votes that were never stored are not listed
+					if runtime_support::storage::exists(&key) {
+						unmigrated.push(id);
+					}
+					continue;
+				},
+			};
+			let record = VoteRecord {
+				id: legacy.id,
+				data: VoteData {
+					initiator: legacy.data.initiator,
+					stage: legacy.data.stage,
+					vote_type: legacy.data.vote_type,
+					tally_type: legacy.data.tally_type,
+					is_commit_reveal: legacy.data.is_commit_reveal,
+					merkle_root: None,
+					rules: Default::default(),
+				},
+				outcomes: legacy.outcomes,
+			};
+			legacy.commitments.into_iter()
+				.for_each(|(who, commit)| { Self::insert_commitment(id, &who, commit); });
+			legacy.reveals.iter()
+				.for_each(|(who, outcome)| { Self::insert_reveal(id, who, vec![*outcome]); });
+			// the old records kept no running totals
+			legacy.reveals.iter().for_each(|(who, _)| {
+				Self::recount(&record, who);
+				Self::queue_recount(Recount::Delegators(id, who.clone(), 0));
 			});
+			if record.data.stage == VoteStage::Voting {
+				<ActiveVotes<T>>::mutate(|votes| votes.push(id));
+			}
+			<VoteRecords<T>>::insert(id, record);
+		}
+
+		if unmigrated.is_empty() {
+			<StorageVersion<T>>::put(1);
+		} else {
+			<UnmigratedVotes<T>>::put(unmigrated);
+		}
+	}
+
+	// counts each ballot's weight towards the first of its choices that is
+	// still standing, giving zero to every eliminated outcome
+	fn count_first_choices(
+		outcomes: &Vec<VoteOutcome>,
+		ballots: &Vec<(Ballot, T::Balance)>,
+		eliminated: &Vec<VoteOutcome>
+	) -> Vec<(VoteOutcome, T::Balance)> {
+		let mut counts: Vec<(VoteOutcome, T::Balance)> = outcomes
+			.clone()
+			.into_iter()
+			.map(|o| (o, Zero::zero()))
+			.collect();
+
+		for (ballot, weight) in ballots.iter() {
+			if let Some(selection) = ballot.iter().find(|o| !eliminated.contains(o)) {
+				// revealed ballots only hold the vote's outcomes
+				if let Some(index) = counts.iter().position(|o| &o.0 == selection) {
+					counts[index].1 = counts[index].1.saturating_add(*weight);
+				}
+			}
+		}
 
-			// populate the map
-			record.reveals.clone().into_iter()
-				.for_each(|r| Self::build_rep_map(&mut reps, &voters, r.0));
+		counts
+	}
+
+	// counts each ballot's weight towards every outcome it approves
+	fn count_approvals(
+		outcomes: &Vec<VoteOutcome>,
+		ballots: &Vec<(Ballot, T::Balance)>
+	) -> Vec<(VoteOutcome, T::Balance)> {
+		outcomes.iter().map(|o| {
+			let approvals = ballots.iter()
+				.filter(|(ballot, _)| ballot.contains(o))
+				.fold(Zero::zero(), |acc: T::Balance, (_, w)| acc.saturating_add(*w));
+			(*o, approvals)
+		}).collect()
+	}
+
+	// runs instant-runoff elimination rounds until an outcome holds a majority
+	// of the ballots that are not yet exhausted, or only one outcome remains.
+	// Outcomes with no weight move no ballots when eliminated, so each round
+	// eliminates all of them together with the weakest outcome.
+	fn compute_runoff_rounds(vote_id: u64) -> Option<Vec<RunoffRound<T::Balance>>> {
+		let record = <VoteRecords<T>>::get(vote_id)?;
+		let ballots = Self::ballot_totals(vote_id);
+		let mut eliminated: Vec<VoteOutcome> = vec![];
+		let mut rounds = vec![];
 
-			// tally up the vote
-			let mut outcomes: Vec<(VoteOutcome, T::Balance)> = record.outcomes
-				.clone()
+		loop {
+			let counts = Self::count_first_choices(&record.outcomes, &ballots, &eliminated);
+			let standing: Vec<(VoteOutcome, T::Balance)> = counts.clone()
 				.into_iter()
-				.map(|o| (o, Zero::zero()))
+				.filter(|(o, _)| !eliminated.contains(o))
 				.collect();
 
-			for (account, rep) in reps.iter() {
-				let weight: T::Balance = match record.data.tally_type {
-					TallyType::OnePerson => One::one(),
-					TallyType::OneCoin => <balances::Module<T>>::free_balance(account),
-				};
+			let active: T::Balance = standing.iter()
+				.fold(Zero::zero(), |acc: T::Balance, (_, w)| acc.saturating_add(*w));
+			let leader: T::Balance = standing.iter()
+				.fold(Zero::zero(), |acc: T::Balance, (_, w)| if *w > acc { *w } else { acc });
+			if standing.len() <= 1 || active.is_zero() || leader > active.saturating_sub(leader) {
+				rounds.push(RunoffRound { counts, eliminated: vec![] });
+				break;
+			}
+
+			// eliminate the weakest outcome with weight, the last listed one on
+			// ties, and every outcome without any
+			let weakest = standing.iter()
+				.filter(|(_, w)| !w.is_zero())
+				.fold(None, |low: Option<(VoteOutcome, T::Balance)>, (o, w)| match low {
+					Some((_, lw)) if *w > lw => low,
+					_ => Some((*o, *w)),
+				});
+			let losers: Vec<VoteOutcome> = standing.iter()
+				.filter(|(o, w)| w.is_zero() || weakest.map_or(false, |(loser, _)| loser == *o))
+				.map(|(o, _)| *o)
+				.collect();
+			eliminated.extend(losers.iter().cloned());
+			rounds.push(RunoffRound { counts, eliminated: losers });
+		}
+
+		Some(rounds)
+	}
+
+	/// Elects the seats of a committee election by sequential Phragmen. Each
+	/// round elects the outcome whose approvers would end up with the lowest
+	/// load, then raises its approvers' loads to that score. Weights are
+	/// scaled into 32 bits first, so accounts holding less than a 2^-32 share
+	/// of the counted weight only elect outcomes nobody else approves. This
+	/// walks every account counted on the vote and is meant for clients; the
+	/// chain only keeps how many seats were filled.
+	pub fn compute_committee(vote_id: u64) -> Option<CommitteeResult<T::AccountId, T::Balance>> {
+		let record = <VoteRecords<T>>::get(vote_id)?;
+		let seats = match record.data.vote_type {
+			VoteType::Committee(seats) => seats,
+			_ => return None,
+		};
+		let voters: Vec<(T::AccountId, Ballot, T::Balance)> = Self::compute_breakdown(vote_id).into_iter()
+			.map(|(who, _, ballot, weight)| (who, ballot, weight))
+			.collect();
+		let total = voters.iter().fold(Zero::zero(), |acc: T::Balance, (_, _, w)| acc.saturating_add(*w));
+		let divisor = total / <T::Balance as As<u64>>::sa(u32::max_value() as u64) + One::one();
+		let weights: Vec<u128> = voters.iter().map(|(_, _, w)| (*w / divisor).as_() as u128).collect();
+
+		let mut loads: Vec<u128> = vec![0; voters.len()];
+		// the load each voter took on for each outcome it helped elect
+		let mut edges: Vec<Vec<(VoteOutcome, u128)>> = vec![vec![]; voters.len()];
+		let mut elected: Vec<VoteOutcome> = vec![];
+		for _ in 0..seats {
+			let mut best: Option<(VoteOutcome, u128)> = None;
+			for outcome in record.outcomes.iter().filter(|o| !elected.contains(o)) {
+				if !voters.iter().any(|(_, ballot, weight)| ballot.contains(outcome) && !weight.is_zero()) {
+					continue;
+				}
+				let (support, weighted_load) = voters.iter().enumerate()
+					.filter(|(_, (_, ballot, _))| ballot.contains(outcome))
+					.fold((0u128, 0u128), |(s, l), (i, _)| (
+						s.saturating_add(weights[i]),
+						l.saturating_add(weights[i].saturating_mul(loads[i]))
+					));
+				// the first listed outcome wins ties
+				let score = PHRAGMEN_SCALE.saturating_add(weighted_load) / rstd::cmp::max(support, 1);
+				if best.map_or(true, |(_, lowest)| score < lowest) {
+					best = Some((*outcome, score));
+				}
+			}
 
-				// use the representative's choice and the voter's weight
-				let (_, selection) = voters.iter().find(|(v, _)| &v == &rep).unwrap();
-				let index: usize = outcomes.iter().position(|&o| &o.0 == selection).unwrap();
-				outcomes[index].1 = outcomes[index].1.checked_add(&weight).unwrap();
+			let (winner, score) = match best {
+				Some(best) => best,
+				None => break,
+			};
+			for (i, (_, ballot, _)) in voters.iter().enumerate() {
+				if ballot.contains(&winner) {
+					edges[i].push((winner, score.saturating_sub(loads[i])));
+					loads[i] = score;
+				}
 			}
+			elected.push(winner);
+		}
 
-			return Some(outcomes);
+		let distribution = voters.into_iter()
+			.zip(edges.into_iter())
+			.zip(loads.into_iter())
+			.filter(|(_, load)| *load > 0)
+			.map(|(((who, _, weight), edges), load)| {
+				let stakes = edges.into_iter()
+					.map(|(outcome, edge)| (outcome, Self::portion_of(weight, edge, load)))
+					.collect();
+				(who, stakes)
+			})
+			.collect();
+		Some(CommitteeResult { elected, distribution })
+	}
+
+	// finds the lowest value on a scalar vote with at least half of the
+	// counted weight at or below it, paired with the whole counted weight
+	fn weighted_median(ballots: &Vec<(Ballot, T::Balance)>) -> Option<(VoteOutcome, T::Balance)> {
+		let mut values: Vec<(VoteOutcome, T::Balance)> = ballots.iter()
+			.filter(|(_, weight)| !weight.is_zero())
+			.filter_map(|(ballot, weight)| ballot.first().map(|v| (*v, *weight)))
+			.collect();
+		// encoded values sort in numeric order
+		values.sort_by(|a, b| a.0.cmp(&b.0));
+		let total = values.iter().fold(Zero::zero(), |acc: T::Balance, (_, w)| acc.saturating_add(*w));
+
+		let mut below: T::Balance = Zero::zero();
+		for (value, weight) in values.into_iter() {
+			below = below.saturating_add(weight);
+			if below >= total.saturating_sub(below) {
+				return Some((value, total));
+			}
 		}
+		None
+	}
 
-		return None;
+	// `amount * part / whole` for `part <= whole`, accurate to 2^-32
+	fn portion_of(amount: T::Balance, part: u128, whole: u128) -> T::Balance {
+		let unit: u64 = 1 << 32;
+		let ratio = <T::Balance as As<u64>>::sa((part.saturating_mul(unit as u128) / whole) as u64);
+		let unit = <T::Balance as As<u64>>::sa(unit);
+		(amount / unit).saturating_mul(ratio).saturating_add(amount % unit * ratio / unit)
+	}
+
+	pub fn tally(vote_id: u64) -> Tally<T::Balance> {
+		if let Some(record) = <VoteRecords<T>>::get(vote_id) {
+			// cancelled votes decide nothing
+			if record.data.stage == VoteStage::Cancelled {
+				return None;
+			}
+
+			// multi option votes report the final runoff round
+			if record.data.vote_type == VoteType::MultiOption {
+				return Self::compute_runoff_rounds(vote_id).and_then(|mut rounds| rounds.pop()).map(|round| round.counts);
+			}
+
+			// tally up the running totals
+			let ballots = Self::ballot_totals(vote_id);
+			return Some(match record.data.vote_type {
+				VoteType::Approval | VoteType::Committee(_) => Self::count_approvals(&record.outcomes, &ballots),
+				// scalar votes report their weighted median with the counted weight
+				VoteType::Scalar(..) => Self::weighted_median(&ballots).into_iter().collect(),
+				_ => Self::count_first_choices(&record.outcomes, &ballots, &vec![]),
+			});
+		}
+
+		// pruned votes keep their final tally
+		return Self::pruned_vote(vote_id).and_then(|pruned| pruned.tally);
+	}
+
+	// whether `part` is at least `percent` percent of `whole`
+	fn meets_percent(part: T::Balance, whole: T::Balance, percent: u32) -> bool {
+		let hundred = <T::Balance as As<u64>>::sa(100);
+		let percent = <T::Balance as As<u64>>::sa(percent as u64);
+		match (part.checked_mul(&hundred), whole.checked_mul(&percent)) {
+			(Some(p), Some(w)) => p >= w,
+			// scale both sides down rather than overflow
+			_ => part / hundred >= whole / hundred * percent / hundred,
+		}
+	}
+
+	/// The result of a completed vote under its quorum and threshold rules.
+	/// Turnout counts every account whose weight reached a ballot, directly or
+	/// through delegation. Returns `None` until the vote has completed.
+	pub fn result(vote_id: u64) -> Option<VoteResult> {
+		let record = <VoteRecords<T>>::get(vote_id)?;
+		if record.data.stage != VoteStage::Completed {
+			return None;
+		}
+
+		let (accounts, turnout) = Self::turnout(vote_id);
+		let quorum_met = match record.data.rules.quorum {
+			Some(Quorum::PercentOfIssuance(percent)) =>
+				Self::meets_percent(turnout, <balances::Module<T>>::total_issuance(), percent),
+			Some(Quorum::PercentOfAccounts(percent, eligible)) =>
+				(accounts as u128) * 100 >= (eligible as u128) * (percent as u128),
+			None => true,
+		};
+		if !quorum_met {
+			return Some(VoteResult::NoQuorum);
+		}
+		// committee elections fill seats rather than meet a threshold. Every
+		// round of `compute_committee` elects an outcome while any outcome
+		// left has support, so the seats filled follow from the totals.
+		if let VoteType::Committee(seats) = record.data.vote_type {
+			let totals = Self::ballot_totals(vote_id);
+			let supported = record.outcomes.iter()
+				.filter(|o| totals.iter().any(|(ballot, weight)| ballot.contains(*o) && !weight.is_zero()))
+				.count() as u32;
+			let filled = rstd::cmp::min(seats, supported);
+			return Some(if filled > 0 { VoteResult::Elected(filled) } else { VoteResult::Failed });
+		}
+
+		let tally = Self::tally(vote_id)?;
+		// approval ballots count towards several outcomes, so measure the
+		// leader against the turnout rather than the sum of the tally
+		let total: T::Balance = if record.data.vote_type == VoteType::Approval {
+			turnout
+		} else {
+			tally.iter().fold(Zero::zero(), |acc: T::Balance, (_, w)| acc.saturating_add(*w))
+		};
+		let leader: T::Balance = tally.iter()
+			.fold(Zero::zero(), |acc: T::Balance, (_, w)| if *w > acc { *w } else { acc });
+		let leaders: Vec<VoteOutcome> = tally.iter()
+			.filter(|(_, w)| *w == leader)
+			.map(|(o, _)| *o)
+			.collect();
+		// a tie at the top decides nothing
+		if leader.is_zero() || leaders.len() != 1 {
+			return Some(VoteResult::Failed);
+		}
+
+		let passed = match record.data.rules.threshold {
+			Threshold::SimpleMajority => leader > total.saturating_sub(leader),
+			Threshold::Supermajority(percent) => Self::meets_percent(leader, total, percent),
+			Threshold::Absolute(amount) => leader >= amount,
+		};
+		Some(if passed { VoteResult::Passed(leaders[0]) } else { VoteResult::Failed })
+	}
+}
+
+impl<T: Trait> delegation::OnDelegationChange<T::AccountId> for Module<T> {
+	fn on_delegation_change(who: &T::AccountId) {
+		Self::queue_recount(Recount::Delegation(who.clone(), 0));
+		Self::process_recounts(MAX_RECOUNTS_PER_CALL);
+	}
+}
+
+impl<T: Trait> balances::EnsureAccountLiquid<T::AccountId> for Module<T> {
+	fn ensure_account_liquid(who: &T::AccountId) -> Result {
+		// locks on cancelled votes are released lazily, and completed votes
+		// keep their voters locked until their lock periods are started
+		let is_locked = Self::pending_locks(who).into_iter()
+			.any(|id| Self::vote_records(id).map_or(false, |r| r.data.stage != VoteStage::Cancelled));
+		ensure!(!is_locked, "Account is locked by a conviction vote in progress");
+		ensure!(Self::bondage(who) <= <system::Module<T>>::block_number(), "Account is locked by a conviction vote");
+		Self::note_balance_move(who);
+		Ok(())
 	}
 }
 
 /// An event in this module.
 decl_event!(
-	pub enum Event<T> where <T as system::Trait>::AccountId {
+	pub enum Event<T> where <T as system::Trait>::AccountId, <T as balances::Trait>::Balance {
 		/// new vote (id, creator, type of vote)
 		VoteCreated(u64, AccountId, VoteType),
 		/// vote stage transition (id, old stage, new stage)
@@ -315,15 +2321,159 @@ decl_event!(
 		/// user commits
 		VoteCommitted(u64, AccountId),
 		/// user reveals a vote
-		VoteRevealed(u64, AccountId, VoteOutcome),
+		VoteRevealed(u64, AccountId, Ballot),
+		/// user replaces a revealed vote (id, voter, old vote, new vote)
+		VoteChanged(u64, AccountId, Ballot, Ballot),
+		/// unsigned reveal on a ring or merkle vote (id, key image or nullifier, vote)
+		AnonymousVoteRevealed(u64, PublicKey, Ballot),
+		/// vote withdrawn (id, stage it was cancelled in)
+		VoteCancelled(u64, VoteStage),
+		/// commitment bond returned on reveal or cancellation (id, voter, bond)
+		CommitBondReturned(u64, AccountId, Balance),
+		/// commitment bond slashed from a voter who never revealed (id, voter, amount slashed)
+		CommitBondSlashed(u64, AccountId, Balance),
+		/// creation deposit returned on completion or cancellation by the initiator (id, initiator, deposit)
+		CreationDepositReturned(u64, AccountId, Balance),
+		/// creation deposit slashed on a forced cancellation (id, initiator, amount slashed)
+		CreationDepositSlashed(u64, AccountId, Balance),
+		/// finished vote compacted after its retention period (id)
+		VotePruned(u64),
+		/// relayed ballot skipped for a bad nonce or signature or a failed reveal (id, signer, nonce)
+		SignedBallotRejected(u64, AccountId, u64),
+		/// encrypted ballot cast or replaced (id, voter)
+		EncryptedVoteCast(u64, AccountId),
+		/// encrypted ballot that did not decrypt to a valid ballot (id, voter)
+		EncryptedBallotDiscarded(u64, AccountId),
+		/// counted weight moved to what the account's balance is worth now (id, account, new weight)
+		WeightRefreshed(u64, AccountId, Balance),
+		/// threshold key of the authorities replaced (public key)
+		ThresholdKeyChanged(PublicKey),
+		/// decryption deadline passed with ballots still encrypted, which are left out (id, ballots left out)
+		DecryptionExpired(u64, u64),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Voting {
 		/// The map of all vote records indexed by id
-		pub VoteRecords get(vote_records): map u64 => Option<VoteRecord<T::AccountId>>;
+		pub VoteRecords get(vote_records): map u64 => Option<VoteRecord<T::AccountId, T::Balance>>;
+		/// The commitment each account made on each commit-reveal vote
+		pub Commitments get(commitment_of): map (u64, T::AccountId) => Option<VoteOutcome>;
+		/// The number of accounts that committed on each vote
+		pub CommitmentCount get(commitment_count): map u64 => u64;
+		/// The accounts that committed on each vote, in the order they first committed
+		pub Committers get(committer): map (u64, u64) => Option<T::AccountId>;
+		/// The balance reserved by each account's first commitment on a vote
+		pub CommitBond get(commit_bond) config(): T::Balance;
+		/// The commitment bonds still held on each vote, until revealed or slashed
+		pub CommitBonds get(commit_bond_of): map (u64, T::AccountId) => Option<T::Balance>;
+		/// The accounts allowed to vote on each vote with an allowlist policy
+		pub Allowlist get(is_allowed): map (u64, T::AccountId) => bool;
+		/// The accounts added to each vote's allowlist, in the order they were added
+		pub Allowlisted get(allowlisted): map u64 => Vec<T::AccountId>;
+		/// The ballot each account revealed on each vote
+		pub Reveals get(reveal_of): map (u64, T::AccountId) => Option<Ballot>;
+		/// The number of accounts that revealed on each vote
+		pub RevealCount get(reveal_count): map u64 => u64;
+		/// The accounts that revealed on each vote, in the order they first revealed
+		pub Voters get(voter): map (u64, u64) => Option<T::AccountId>;
+		/// The balance reserved by an account creating a vote through `create_vote`
+		pub CreationDeposit get(creation_deposit) config(): T::Balance;
+		/// The most outcomes a vote created through `create_vote` may have
+		pub MaxOutcomes get(max_outcomes) config(): u32;
+		/// The initiator and deposit reserved for each vote until it finishes
+		pub CreationDeposits get(creation_deposit_of): map u64 => Option<(T::AccountId, T::Balance)>;
 		/// The number of vote records that have been created
 		pub VoteRecordCount get(vote_record_count): u64;
+		/// The layout version of the stored vote records
+		pub StorageVersion get(storage_version): u32;
+		/// The vote records that did not decode as the layout before version 1,
+		/// left as they were by the migration
+		pub UnmigratedVotes get(unmigrated_votes): Vec<u64>;
+		/// The number of blocks in one conviction lock period
+		pub LockPeriod get(lock_period) config(): T::BlockNumber;
+		/// The most lock periods a voter may choose on a conviction vote
+		pub MaxLockPeriods get(max_lock_periods) config(): u8;
+		/// The lock periods each voter chose on each conviction vote
+		pub Convictions get(conviction_of): map (u64, T::AccountId) => u8;
+		/// The unfinished conviction votes each account has locked its balance for
+		pub PendingLocks get(pending_locks): map T::AccountId => Vec<u64>;
+		/// The block until which each account's balance is locked by completed conviction votes
+		pub Bondage get(bondage): map T::AccountId => T::BlockNumber;
+		/// The ballot and weight each account is currently counted with on each vote
+		pub Contributions get(contribution_of): map (u64, T::AccountId) => Option<(Ballot, T::Balance)>;
+		/// The votes each account is counted on, including finished ones not yet dropped
+		pub CountedOn get(counted_on): map T::AccountId => Vec<u64>;
+		/// The accounts counted on open votes whose coins may have moved since their
+		/// weights were last settled
+		pub MovedBalances get(moved_balances): Vec<T::AccountId>;
+		/// The number of times an account started being counted on each vote
+		pub ContributorCount get(contributor_count): map u64 => u64;
+		/// The accounts that have been counted on each vote, in the order they were first counted
+		pub Contributors get(contributor): map (u64, u64) => Option<T::AccountId>;
+		/// The running weight behind each distinct ballot on each vote
+		pub BallotTotals get(ballot_totals): map u64 => Vec<(Ballot, T::Balance)>;
+		/// The number of accounts counted on each vote and their total weight
+		pub Turnout get(turnout): map u64 => (u64, T::Balance);
+		/// The public votes currently in their voting stage
+		pub ActiveVotes get(active_votes): Vec<u64>;
+		/// The queue position of the next recount to work on
+		pub RecountHead get(recount_head): u64;
+		/// The queue position the next queued recount is stored at
+		pub RecountTail get(recount_tail): u64;
+		/// New voters' delegators, delegation changes and conviction locks still to be
+		/// worked through, by queue position
+		pub Recounts get(queued_recount): map u64 => Option<Recount<T::AccountId>>;
+		/// The number of queued recounts of each vote's delegators, which must reach
+		/// zero before the vote completes
+		pub PendingRecounts get(pending_recounts): map u64 => u64;
+		/// The stage schedule of votes that advance automatically
+		pub VoteSchedules get(vote_schedule): map u64 => Option<VoteSchedule<T::BlockNumber, T::Moment>>;
+		/// The scheduled votes that have not yet completed
+		pub ScheduledVotes get(scheduled_votes): Vec<u64>;
+		/// The ring of public keys allowed to vote on each anonymous ring vote
+		pub VoteRings get(vote_ring): map u64 => Vec<PublicKey>;
+		/// The key images that have already voted on each anonymous ring vote
+		pub KeyImages get(key_image_used): map (u64, PublicKey) => bool;
+		/// The nullifiers that have already voted on each merkle membership vote
+		pub Nullifiers get(nullifier_used): map (u64, [u8; 32]) => bool;
+		/// Ballots revealed on ring and merkle votes, tagged by key image or nullifier
+		pub AnonymousReveals get(anonymous_reveals): map u64 => Vec<(PublicKey, Ballot)>;
+		/// The instant-runoff rounds of completed multi option votes and the outcomes
+		/// each round eliminated, first round first
+		pub RunoffRounds get(runoff_rounds): map u64 => Option<Vec<RunoffRound<T::Balance>>>;
+		/// The stages each vote entered and when, in order
+		pub StageTimes get(stage_times): map u64 => Vec<(VoteStage, T::Moment)>;
+		/// The number of blocks finished votes are kept before being pruned, zero to keep them forever
+		pub RetentionPeriod get(retention_period) config(): T::BlockNumber;
+		/// The votes that completed or were cancelled and the block they finished in, oldest first
+		pub FinishedVotes get(finished_votes): Vec<(u64, T::BlockNumber)>;
+		/// The finished vote being pruned, resumed each block until its storage is gone
+		pub Pruning get(pruning): Option<PruneCursor<T::Hash>>;
+		/// The compacted form of each pruned vote
+		pub PrunedVotes get(pruned_vote): map u64 => Option<PrunedVote<T::Balance, T::Hash, T::Moment>>;
+		/// The nonce each account's next relayed ballot must be signed with
+		pub BallotNonces get(ballot_nonce): map T::AccountId => u64;
+		/// The threshold key held by the current authorities, used by new encrypted votes
+		pub AuthorityKey get(authority_key): Option<ThresholdKey>;
+		/// The threshold key each encrypted vote's ballots are encrypted to
+		pub VoteKeys get(vote_key): map u64 => Option<ThresholdKey>;
+		/// The encrypted ballots of each voter that are not yet decrypted
+		pub Ciphertexts get(ciphertext_of): map (u64, T::AccountId) => Option<Ciphertext>;
+		/// The number of accounts that cast encrypted ballots on each vote
+		pub CiphertextCount get(ciphertext_count): map u64 => u64;
+		/// The accounts that cast encrypted ballots on a vote, in the order they first cast one
+		pub EncryptedVoters get(encrypted_voter): map (u64, u64) => Option<T::AccountId>;
+		/// The number of encrypted ballots on each vote still waiting to be decrypted
+		pub PendingDecryptions get(pending_decryptions): map u64 => u64;
+		/// The verified decryption shares received for each encrypted ballot
+		pub DecryptionShares get(decryption_shares_of): map (u64, T::AccountId) => Vec<DecryptionShare>;
+		/// The number of blocks a vote waits for its ballots to be decrypted before
+		/// completing without the rest, zero to wait until they all are
+		pub DecryptionPeriod get(decryption_period) config(): T::BlockNumber;
+		/// The block by which each decrypting vote completes, decrypted or not
+		pub DecryptionDeadlines get(decryption_deadline): map u64 => Option<T::BlockNumber>;
+		/// The votes waiting for their ballots to be decrypted
+		pub DecryptingVotes get(decrypting_votes): Vec<u64>;
 	}
 }
@@ -59,7 +59,7 @@ use runtime_primitives::ApplyResult;
 use runtime_primitives::transaction_validity::TransactionValidity;
 use runtime_primitives::generic;
 use runtime_primitives::traits::{
-	Convert, BlakeTwo256, Block as BlockT, DigestFor, NumberFor, StaticLookup,
+	Convert, BlakeTwo256, Block as BlockT, DigestFor, NumberFor, StaticLookup, EnsureOrigin,
 };
 use grandpa::fg_primitives::{self, ScheduledChange};
 use version::RuntimeVersion;
@@ -127,7 +127,7 @@ impl balances::Trait for Runtime {
 	type Balance = Balance;
 	type OnFreeBalanceZero = ((Staking, Contract), Democracy);
 	type OnNewAccount = Indices;
-	type EnsureAccountLiquid = (Staking, Democracy);
+	type EnsureAccountLiquid = ((Staking, Democracy), Voting);
 	type Event = Event;
 }
 
@@ -214,11 +214,50 @@ impl upgrade_key::Trait for Runtime {
 impl delegation::Trait for Runtime {
 	/// The uniquitous event type.
 	type Event = Event;
+	type OnDelegationChange = Voting;
+}
+
+/// Approves accounts holding a verified identity to vote on votes whose
+/// eligibility policy defers to the runtime.
+pub struct VerifiedIdentities;
+impl voting::VoterEligibility<AccountId> for VerifiedIdentities {
+	fn can_vote(_vote_id: u64, who: &AccountId) -> bool {
+		Identity::is_verified(who)
+	}
+}
+
+/// Accepts only the root origin, reached through referenda, for the voting
+/// parameters and the threshold key encrypted ballots are kept secret with.
+pub struct EnsureRoot;
+impl EnsureOrigin<Origin> for EnsureRoot {
+	type Success = ();
+	fn ensure_origin(o: Origin) -> rstd::result::Result<(), &'static str> {
+		system::ensure_root(o)
+	}
+}
+
+/// Pays the commitment bonds slashed from voters who never revealed into the
+/// treasury pot.
+pub struct BondsToTreasury;
+impl voting::OnBondSlashed<Balance> for BondsToTreasury {
+	fn on_bond_slashed(amount: Balance) {
+		// the slashed balance has already left the issuance, so the pot takes
+		// it over as is. The treasury has no call for funding its pot, so it
+		// is reached through the storage trait `decl_storage!` makes public.
+		<Treasury as treasury::Store>::Pot::mutate(|pot| *pot = pot.saturating_add(amount));
+	}
 }
 
 impl voting::Trait for Runtime {
 	/// The uniquitous event type.
 	type Event = Event;
+	type CancelOrigin = council_motions::EnsureMembers<_2>;
+	type Eligibility = VerifiedIdentities;
+	type OnBondSlashed = BondsToTreasury;
+	type OnVoteCompleted = Governance;
+	type Signature = Signature;
+	type KeyOrigin = EnsureRoot;
+	type ParameterOrigin = EnsureRoot;
 }
 
 impl governance::Trait for Runtime {
@@ -256,7 +295,7 @@ construct_runtime!(
 		UpgradeKey: upgrade_key,
 		Identity: identity::{Module, Call, Storage, Config<T>, Event<T>},
 		Delegation: delegation::{Module, Call, Storage, Config<T>, Event<T>},
-		Voting: voting::{Module, Call, Storage, Event<T>},
+		Voting: voting::{Module, Call, Storage, Config<T>, Event<T>},
 		Governance: governance::{Module, Call, Storage, Config<T>, Event<T>},
 	}
 );
@@ -327,6 +366,12 @@ impl_runtime_apis! {
 
 	impl client_api::TaggedTransactionQueue<Block> for Runtime {
 		fn validate_transaction(tx: <Block as BlockT>::Extrinsic) -> TransactionValidity {
+			// anonymous ballots have no sender, so the voting module checks them
+			if tx.signature.is_none() {
+				if let Call::Voting(ref call) = tx.function {
+					return Voting::validate_unsigned(call);
+				}
+			}
 			Executive::validate_transaction(tx)
 		}
 	}
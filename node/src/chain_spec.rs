@@ -2,7 +2,7 @@ use edgeware_runtime::{
 	Permill, Perbill,
 	BalancesConfig, ConsensusConfig, GenesisConfig, ContractConfig, SessionConfig,
 	TimestampConfig, TreasuryConfig, StakingConfig, UpgradeKeyConfig, GrandpaConfig,
-	IdentityConfig, GovernanceConfig, DelegationConfig,
+	IdentityConfig, GovernanceConfig, DelegationConfig, VotingConfig,
 	CouncilSeatsConfig, CouncilVotingConfig, DemocracyConfig, IndicesConfig,
 };
 use node_primitives::AccountId;
@@ -207,5 +207,14 @@ fn testnet_genesis(
 			delegation_depth: 5,
 			_genesis_phantom_data: Default::default(),
 		}),
+		voting: Some(VotingConfig {
+			lock_period: 17280, // 1 day
+			max_lock_periods: 6,
+			commit_bond: 1000,
+			creation_deposit: 1_000_000,
+			max_outcomes: 16,
+			retention_period: 241920, // 14 days
+			decryption_period: 17280, // 1 day
+		}),
 	}
 }
@@ -3,11 +3,43 @@ extern crate substrate_cli as cli;
 use service;
 use futures::{Future};
 use tokio::runtime::Runtime;
-pub use substrate_cli::{VersionInfo, IntoExit, NoCustom};
+pub use substrate_cli::{VersionInfo, IntoExit, NoCustom, GetLogFilter};
 use substrate_service::{ServiceFactory, Roles as ServiceRoles};
 use chain_spec;
 use std::ops::Deref;
 pub use substrate_cli::error;
+use edge_voting::compute_commitment;
+use node_primitives::AccountId;
+use runtime_primitives::traits::BlakeTwo256;
+use primitives::H256;
+
+/// Subcommands of the Edgeware node besides the ones every Substrate node has.
+#[derive(Clone, Debug, StructOpt)]
+pub enum CustomSubcommands {
+	/// Print the commitment to submit on a commit-reveal vote, and the secret to reveal it with.
+	#[structopt(name = "vote-commitment")]
+	VoteCommitment(VoteCommitmentCmd),
+}
+
+impl GetLogFilter for CustomSubcommands {
+	fn get_log_filter(&self) -> Option<String> {
+		None
+	}
+}
+
+/// The `vote-commitment` command.
+#[derive(Clone, Debug, StructOpt)]
+pub struct VoteCommitmentCmd {
+	/// Hex encoded account that will commit and reveal
+	#[structopt(long = "account")]
+	pub account: String,
+	/// Hex encoded outcome, repeated for ranked or approval ballots in ballot order
+	#[structopt(long = "outcome")]
+	pub outcomes: Vec<String>,
+	/// Hex encoded 32 byte secret, a random one is generated if omitted
+	#[structopt(long = "secret")]
+	pub secret: Option<String>,
+}
 
 /// Parse command line arguments into service configuration.
 pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Result<()> where
@@ -15,7 +47,7 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 	T: Into<std::ffi::OsString> + Clone,
 	E: IntoExit,
 {
-	cli::parse_and_execute::<service::Factory, NoCustom, NoCustom, _, _, _, _, _>(
+	cli::parse_and_execute::<service::Factory, CustomSubcommands, NoCustom, _, _, _, _, _>(
 		load_spec, &version, "substrate-node", args, exit,
 		|exit, _custom_args, config| {
 			info!("{}", version.name);
@@ -39,7 +71,52 @@ pub fn run<I, T, E>(args: I, exit: E, version: cli::VersionInfo) -> error::Resul
 				),
 			}.map_err(|e| format!("{:?}", e))
 		}
-	).map_err(Into::into).map(|_| ())
+	).map_err(Into::into).and_then(|custom| match custom {
+		Some(CustomSubcommands::VoteCommitment(cmd)) => print_vote_commitment(cmd),
+		None => Ok(()),
+	})
+}
+
+fn print_vote_commitment(cmd: VoteCommitmentCmd) -> error::Result<()> {
+	let secret = match cmd.secret {
+		Some(ref secret) => parse_hex_32(secret)?,
+		None => rand::random(),
+	};
+	let commitment = vote_commitment(&cmd, &secret)?;
+	println!("Commitment: 0x{}", to_hex(commitment.as_ref()));
+	println!("Secret: 0x{}", to_hex(&secret));
+	Ok(())
+}
+
+// the commitment the account of `cmd` submits to reveal its outcomes with `secret`
+fn vote_commitment(cmd: &VoteCommitmentCmd, secret: &[u8; 32]) -> error::Result<H256> {
+	let account = AccountId::from(parse_hex_32(&cmd.account)?);
+	let ballot = cmd.outcomes.iter()
+		.map(|outcome| parse_hex_32(outcome))
+		.collect::<Result<Vec<_>, _>>()?;
+	if ballot.is_empty() {
+		return Err("At least one --outcome is required".into());
+	}
+
+	Ok(compute_commitment::<_, BlakeTwo256>(&account, secret, &ballot))
+}
+
+// parses 32 bytes written as hex, with or without a `0x` prefix
+fn parse_hex_32(s: &str) -> error::Result<[u8; 32]> {
+	let s = s.trim_start_matches("0x");
+	if s.len() != 64 || !s.is_ascii() {
+		return Err(format!("Expected 32 hex encoded bytes, got {}", s).into());
+	}
+	let mut out = [0u8; 32];
+	for (i, byte) in out.iter_mut().enumerate() {
+		*byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+			.map_err(|_| format!("Invalid hex in {}", s))?;
+	}
+	Ok(out)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
@@ -75,4 +152,35 @@ fn run_until_exit<T, C, E>(
 	let _ = runtime.shutdown_on_idle().wait();
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_primitives::traits::Hash;
+
+	#[test]
+	fn vote_commitment_should_hash_account_secret_and_outcomes() {
+		let cmd = VoteCommitmentCmd {
+			account: format!("0x{}", to_hex(&[1; 32])),
+			outcomes: vec![to_hex(&[2; 32]), to_hex(&[3; 32])],
+			secret: None,
+		};
+		let mut preimage = vec![];
+		preimage.extend_from_slice(&[1; 32]);
+		preimage.extend_from_slice(&[9; 32]);
+		preimage.extend_from_slice(&[2; 32]);
+		preimage.extend_from_slice(&[3; 32]);
+		assert_eq!(vote_commitment(&cmd, &[9; 32]).unwrap(), BlakeTwo256::hash_of(&preimage));
+	}
+
+	#[test]
+	fn vote_commitment_should_need_an_outcome() {
+		let cmd = VoteCommitmentCmd {
+			account: to_hex(&[1; 32]),
+			outcomes: vec![],
+			secret: None,
+		};
+		assert!(vote_commitment(&cmd, &[9; 32]).is_err());
+	}
+}
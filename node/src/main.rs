@@ -10,6 +10,10 @@ extern crate tokio;
 #[macro_use]
 extern crate log;
 extern crate substrate_cli;
+#[macro_use]
+extern crate structopt;
+extern crate rand;
+extern crate sr_primitives as runtime_primitives;
 extern crate substrate_primitives as primitives;
 extern crate substrate_consensus_aura as consensus;
 extern crate substrate_client as client;
@@ -23,6 +27,7 @@ extern crate substrate_service;
 extern crate substrate_inherents;
 extern crate substrate_keystore;
 extern crate edgeware_runtime;
+extern crate edge_voting;
 extern crate node_executor;
 extern crate substrate_finality_grandpa as grandpa;
 extern crate node_primitives;